@@ -1,190 +1,1373 @@
-use std::{io::{self, Write}, str::FromStr};
-#[allow(unused_imports)]
-use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
-
-
-pub mod rusty_tasks;
-pub mod rusty_commands;
-pub mod rusty_files;
-
-use rusty_tasks::*;
-use rusty_commands::*;
-use rusty_files::load_tlfromfile;
-
-/// TODO: 
-/// Timezone fix
-/// Add SaveAs and Load commands
-/// SaveAs should allow saving under different filenames
-/// Load command should set the default file to open to the path/file selected
-
-fn get_localtime()->DateTime<Local> {
-    let utc_time: DateTime<Utc> = Utc::now();
-    let local_time: DateTime<Local> = utc_time.with_timezone(&Local);
-    // println!("UTC time: {}", utc_time);
-    // println!("Local time: {}", local_time);
-    return local_time
-}
-
-/// Reads input line from Standard Input and returns it.
-fn read_input_line() -> String {
-    let mut input = String::new();
-    print!("> ");
-    io::stdout().flush().unwrap();
-    std::io::stdin()
-        .read_line(&mut input)
-        .expect("Could not read line.");
-    input
-}
-
-/// On first run shows welcome message
-fn show_welcome_msg(){
-    let help = command_help(None)
-                       .unwrap_or_else(|_|{
-                            eprintln!("Expected error: command_help has failed with 'None' as the parameter, this indicates a DEV bug.");
-                            "".to_string()
-                        });
-    let indent=4;
-    let spacing = " ".repeat(indent);
-    println!(r#"
-{spacing}Welcome to RUSTY TASKS!
-{spacing}=======================
-{spacing}Version 0.0.1
-{spacing}=======================
-
-{}"#,help);
-
-}
-
-/// Starts the terminal input loop, receives, parses, and initiates commands.
-fn run_tasklist(first_run:bool,global_tasks:&mut TaskList,global_datafilepath:String){    
-    if first_run {
-        show_welcome_msg();
-        command_list(global_tasks);
-    }
-
-    loop{
-        let input = read_input_line().trim().to_string();
-        let (command,arguments) = parse_input_commands(&input);
-        
-        let command_enum=match TASKCOM::from_str(command.to_uppercase().as_str()).ok(){
-            Some(t)=>t,
-            None=>{
-                let help = match command_help(None){
-                    Ok(t)=>t,
-                    Err(e)=>format!("There was an error with the help command: {}",e)
-                };
-                eprintln!("Invalid command string, defaulting to HELP.");
-                eprintln!("{}",help);
-                //skip the rest of the loop there is no valid command to handle.
-                continue;
-            }
-        };
-
-        let mut _last_state=command_enum.clone();
-
-        match handle_command(command_enum,arguments,global_tasks,global_datafilepath.clone()){
-            Ok(_)=>{},
-            Err(error)=>{     
-                //println!("Command was: {:?}",command);//debug       
-                eprintln!("Error: {} \r\nLast State: {}",error,_last_state.to_string()) // we bubble these up to here from inside the commands
-            }
-        }
-    }
-}
-
-/// !Creates state object and initiates terminal input loop.
-fn main() {
-    let global_datafilepath:String="data/tasklist.md".to_string();
-    let global_tasklist=&mut load_tlfromfile(global_datafilepath.clone());
-    
-    get_localtime();
-
-    run_tasklist(true,global_tasklist,global_datafilepath.clone());
-}
-
-/// !Start of the testing module for this app
-#[cfg(test)]
-mod tests {
-
-    use crate::rusty_files::*;
-
-    use super::*;
-    use rand::Rng;//import for tests
-
-    /// Prepares mock data and runs some tests.
-    #[test]
-    fn test_runmocktrial(){
-        let mock_tasks=create_mocklist(10);
-        let mut task_list=TaskList{
-            tasks:mock_tasks.to_vec()
-        };
-
-        let initial_length = task_list.tasks.len();
-        
-        let random_task=rand::thread_rng().gen_range(0..task_list.tasks.len());
-        let _  = task_list.delete_task(random_task);
-        assert_eq!(task_list.tasks.len(), initial_length - 1);
-        // Verify tasklist random index is either gone or does not match deleted task
-        if random_task<task_list.tasks.len(){
-        assert!(
-                !task_list.tasks.iter().any(|t| 
-                    task_list.tasks[random_task].data != t.data &&
-                    task_list.tasks[random_task].completed != t.completed
-            ));
-        }
-
-        let random_task2 = rand::thread_rng().gen_range(0..task_list.tasks.len());
-        let original_completed_state = task_list.tasks[random_task2].completed;
-
-        let _ = task_list.toggle_completed_task(random_task2);
-        let updated_completed_state = task_list.tasks[random_task2].completed;
-
-        // Assert that the completed state is inverted
-        assert_ne!(original_completed_state, updated_completed_state);
-
-        let temp_data=String::from("Test Task 1");
-        let temp_task=Task::new(false, temp_data);
-        let added_task_index =task_list.add_task(temp_task).unwrap();
-        // Assert that the task is present in the list at the returned index
-        assert!(added_task_index < task_list.tasks.len(), "Invalid index returned.");
-        assert_eq!(task_list.tasks[added_task_index].data, "Test Task 1");
-    }
-
-    /// Generates a list of fake Tasks for testing.
-    #[allow(dead_code)]
-    fn create_mocklist(num:i32)->Vec<Task>{
-        // Ensure num is positive
-        assert!(num > 0, "num must be a positive integer");
-
-        //functional for loop
-        (1..=num)
-            .map(|i| Task::new(false, format!("Mock Task {}", i)))
-            .collect() 
-    }
-
-    #[test]
-    fn test_createmocklist(){
-        let num = 5;
-        let mock_tasks = create_mocklist(num);
-
-        assert_eq!(num, mock_tasks.len() as i32);
-    }
-
-    #[test]
-    fn test_filesaveload(){        
-        let global_datafilepath:String="testdata/tasklist.md".to_string();
-        let global_tasklist=&mut load_tlfromfile(global_datafilepath.clone());
-        // create mock tasks
-        let _=global_tasklist.add_task(Task::new(false, "test".to_string()));
-        let _=global_tasklist.add_task(Task::new(true, "test2".to_string()));
-        let _=global_tasklist.add_task(Task::new(true, "test3".to_string()));
-        // Testing conversions and file save/load
-        let string_tasklist=convert_tltostring(global_tasklist.clone());
-        let string_totasklist=convert_stringtotl(string_tasklist.clone());
-        println!("STRING: {}\r\n",string_tasklist);
-        println!("TASKLIST: {:?}\r\n",string_totasklist);
-        let _ = save_tltofile(global_datafilepath.clone(), global_tasklist.clone());
-        let new_tasklist=load_tlfromfile(global_datafilepath.clone());
-        println!("TASKLIST after SAVE/LOAD: {:?}",new_tasklist);
-    }
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+#[allow(unused_imports)]
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+
+pub mod rusty_tasks;
+pub mod rusty_commands;
+pub mod rusty_files;
+pub mod rusty_recurrence;
+pub mod rusty_editor;
+pub mod rusty_agenda;
+pub mod rusty_calendar;
+pub mod rusty_tui;
+pub mod rusty_readline;
+pub mod rusty_args;
+pub mod rusty_config;
+pub mod rusty_theme;
+pub mod rusty_table;
+pub mod rusty_pager;
+pub mod rusty_todotxt;
+pub mod rusty_ical;
+pub mod rusty_csv;
+pub mod rusty_serializer;
+pub mod rusty_store;
+pub mod rusty_migrate;
+pub mod rusty_workspace;
+
+use rusty_tasks::*;
+use rusty_commands::*;
+use rusty_files::open_store;
+use rusty_store::{BatchStore, TaskStore};
+use rusty_workspace::Workspace;
+use rusty_tui::run_tui;
+use rusty_readline::{create_editor, read_line};
+use rusty_config::{install_config, load_config, settings};
+use rusty_theme::apply_color_mode;
+
+/// TODO: 
+/// Timezone fix
+fn get_localtime()->DateTime<Local> {
+    let utc_time: DateTime<Utc> = Utc::now();
+    let local_time: DateTime<Local> = utc_time.with_timezone(&Local);
+    // println!("UTC time: {}", utc_time);
+    // println!("Local time: {}", local_time);
+    local_time
+}
+
+/// Reads input line from Standard Input and returns it, the end of input reads as EXIT.
+fn read_input_line(prompt:&str) -> String {
+    let mut input = String::new();
+    print!("{prompt}");
+    io::stdout().flush().unwrap();
+    match std::io::stdin().read_line(&mut input) {
+        Ok(0)=>"exit".to_string(),
+        Ok(_)=>input,
+        Err(error)=>{
+            eprintln!("Could not read line: {error}");
+            "exit".to_string()
+        }
+    }
+}
+
+/// On first run shows welcome message
+fn show_welcome_msg(){
+    let help = command_help(None)
+                       .unwrap_or_else(|_|{
+                            eprintln!("Expected error: command_help has failed with 'None' as the parameter, this indicates a DEV bug.");
+                            "".to_string()
+                        });
+    let indent=settings().indent;
+    let spacing = " ".repeat(indent);
+    let title=settings().welcome_title.clone();
+    let rule="=".repeat(title.chars().count().max(13));
+    println!(r#"
+{spacing}{title}
+{spacing}{rule}
+{spacing}Version 0.0.1
+{spacing}{rule}
+
+{}"#,help);
+
+}
+
+/// Starts the terminal input loop, receives, parses, and initiates commands.
+fn run_tasklist(first_run:bool,global_tasks:&mut TaskList,store:&mut Box<dyn TaskStore>){    
+    if first_run {
+        if settings().show_welcome {
+            show_welcome_msg();
+        }
+        command_list(global_tasks);
+    }
+
+    // fall back to plain stdin when the line editor can't start
+    let mut editor=create_editor()
+        .map_err(|error| eprintln!("Line editing is unavailable: {error}"))
+        .ok();
+
+    loop{
+        // the prompt names the active list
+        let prompt=format!("{}> ",Workspace::from_settings().list_name_for(&store.location()));
+        let input = match editor.as_mut() {
+            Some(editor)=>read_line(editor,global_tasks,&prompt),
+            None=>read_input_line(&prompt)
+        }.trim().to_string();
+        if input.is_empty() {
+            continue;
+        }
+        let aliases=settings().aliases.clone();
+        let (command_enum,arguments)=match parse_command_line(&input,&aliases){
+            Ok(t)=>t,
+            Err(error)=>{
+                eprintln!("{error}");
+                //skip the rest of the loop there is no valid command to handle.
+                continue;
+            }
+        };
+
+        let mut _last_state=command_enum.clone();
+
+        match run_command(command_enum,arguments,global_tasks,store){
+            Ok(_)=>{},
+            Err(error)=>{     
+                //println!("Command was: {:?}",command);//debug       
+                eprintln!("Error: {} \r\nLast State: {}",error,_last_state) // we bubble these up to here from inside the commands
+            }
+        }
+    }
+}
+
+/// Runs commands from a script or piped input without prompts, saving once at the end.
+/// Stops at the first failing line unless keep_going is set, returns false when any line failed.
+fn run_script(reader:impl BufRead,global_tasks:&mut TaskList,store:&mut dyn TaskStore,keep_going:bool)->bool{
+    let mut batch=BatchStore::new(store);
+    let mut failed=false;
+    for (number,line) in reader.lines().enumerate(){
+        let line=match line {
+            Ok(line)=>line,
+            Err(error)=>{
+                eprintln!("Could not read line {}: {error}",number+1);
+                failed=true;
+                break;
+            }
+        };
+        let input=line.trim();
+        // blank lines and # comments keep scripts readable
+        if input.is_empty() || input.starts_with('#') {
+            continue;
+        }
+        let aliases=settings().aliases.clone();
+        let result=parse_command_line(input,&aliases).and_then(|(command,arguments)|{
+            match command {
+                TASKCOM::Exit=>Ok(false),
+                TASKCOM::Tui=>Err("The TUI is not available in batch mode.".to_string()),
+                _=>handle_command(command,arguments,global_tasks,&mut batch).map(|_| true)
+            }
+        });
+        match result {
+            Ok(true)=>{},
+            Ok(false)=>break,
+            Err(error)=>{
+                eprintln!("Line {}: {error}",number+1);
+                failed=true;
+                if !keep_going {
+                    break;
+                }
+            }
+        }
+    }
+
+    let location=batch.location();
+    if failed && !keep_going {
+        eprintln!("Stopped at the first error, changes were not saved. Use --keep-going to run past errors.");
+    }else if let Err(error)=batch.commit() {
+        eprintln!("Could not save {location}: {error}");
+        failed=true;
+    }
+    !failed
+}
+
+/// !Creates state object and initiates terminal input loop.
+fn main() {
+    let args:Vec<String>=std::env::args().collect();
+
+    // --config picks another config file, a broken config stops here instead of half applying
+    let config_path=args.iter().position(|arg| arg == "--config").map(|position| args.get(position+1).map(PathBuf::from));
+    let mut config=match load_config(config_path.clone().flatten()) {
+        Ok(config) if config_path != Some(None)=>config,
+        Ok(_)=>{
+            eprintln!("--config needs a file, like: rusty-tasks --config ~/tasks.toml");
+            std::process::exit(2);
+        },
+        Err(error)=>{
+            eprintln!("Invalid config: {error}");
+            std::process::exit(2);
+        }
+    };
+    // --color=auto|always|never and --plain win over the config file
+    if let Some(mode)=args.iter().find_map(|arg| arg.strip_prefix("--color=")) {
+        if !["auto","always","never"].contains(&mode) {
+            eprintln!("--color must be auto, always or never.");
+            std::process::exit(2);
+        }
+        config.color=mode.to_string();
+    }
+    if args.iter().any(|arg| arg == "--plain") {
+        config.color="never".to_string();
+        config.unicode="never".to_string();
+    }
+    if args.iter().any(|arg| arg == "--strict") {
+        config.strict_load=true;
+    }
+    // a script or piped input runs in batch mode, where echoing every change would bury the errors
+    let batch=args.iter().any(|arg| arg == "--script") || (!io::stdin().is_terminal() && !args.iter().any(|arg| arg == "--tui"));
+    if batch {
+        config.after_change="none".to_string();
+    }
+    apply_color_mode(&config.color);
+    install_config(config);
+    // the list that was active when the app last ran
+    let global_datafilepath:String=Workspace::from_settings().active_list_path();
+    // starting with an empty list would overwrite the file at the first change
+    let (mut store,mut global_tasklist)=match open_store(&global_datafilepath) {
+        Ok(opened)=>opened,
+        Err(error)=>{
+            eprintln!("Could not load {global_datafilepath}: {error}");
+            std::process::exit(2);
+        }
+    };
+    
+    get_localtime();
+
+    let keep_going=args.iter().any(|arg| arg == "--keep-going");
+
+    // --script runs a file of commands, piped input runs the same way
+    if let Some(position)=args.iter().position(|arg| arg == "--script") {
+        let Some(script)=args.get(position+1) else {
+            eprintln!("--script needs a file, like: rusty-tasks --script setup.rtasks");
+            std::process::exit(2);
+        };
+        let ok=match std::fs::File::open(script) {
+            Ok(file)=>run_script(io::BufReader::new(file),&mut global_tasklist,store.as_mut(),keep_going),
+            Err(error)=>{
+                eprintln!("Could not open script {script}: {error}");
+                false
+            }
+        };
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+    if batch {
+        let ok=run_script(io::stdin().lock(),&mut global_tasklist,store.as_mut(),keep_going);
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
+    // --tui starts in the full-screen view, quitting it drops into the REPL
+    if args.iter().any(|arg| arg == "--tui") {
+        if let Err(error)=run_tui(&mut global_tasklist,store.as_mut()) {
+            eprintln!("The TUI failed: {error}");
+        }
+    }
+
+    run_tasklist(true,&mut global_tasklist,&mut store);
+}
+
+/// !Start of the testing module for this app
+#[cfg(test)]
+mod tests {
+
+    use crate::rusty_files::*;
+
+    use super::*;
+    use rand::Rng;//import for tests
+
+    /// Prepares mock data and runs some tests.
+    #[test]
+    fn test_runmocktrial(){
+        let mock_tasks=create_mocklist(10);
+        let mut task_list=TaskList{
+            tasks:mock_tasks.to_vec()
+        };
+
+        let initial_length = task_list.tasks.len();
+        
+        let random_task=rand::thread_rng().gen_range(0..task_list.tasks.len());
+        let _  = task_list.delete_task(random_task);
+        assert_eq!(task_list.tasks.len(), initial_length - 1);
+        // Verify tasklist random index is either gone or does not match deleted task
+        if random_task<task_list.tasks.len(){
+        assert!(
+                !task_list.tasks.iter().any(|t| 
+                    task_list.tasks[random_task].data != t.data &&
+                    task_list.tasks[random_task].completed != t.completed
+            ));
+        }
+
+        let random_task2 = rand::thread_rng().gen_range(0..task_list.tasks.len());
+        let original_completed_state = task_list.tasks[random_task2].completed;
+
+        let _ = task_list.toggle_completed_task(random_task2);
+        let updated_completed_state = task_list.tasks[random_task2].completed;
+
+        // Assert that the completed state is inverted
+        assert_ne!(original_completed_state, updated_completed_state);
+
+        let temp_data=String::from("Test Task 1");
+        let temp_task=Task::new(false, temp_data);
+        let added_task_index =task_list.add_task(temp_task).unwrap();
+        // Assert that the task is present in the list at the returned index
+        assert!(added_task_index < task_list.tasks.len(), "Invalid index returned.");
+        assert_eq!(task_list.tasks[added_task_index].data, "Test Task 1");
+    }
+
+    /// Removes colour codes, so output reads the same whether colour is on for this run or not
+    fn strip_ansi(text:&str)->String{
+        regex::Regex::new("\x1b\\[[0-9;]*m").unwrap().replace_all(text,"").to_string()
+    }
+
+    /// Generates a list of fake Tasks for testing.
+    #[allow(dead_code)]
+    fn create_mocklist(num:i32)->Vec<Task>{
+        // Ensure num is positive
+        assert!(num > 0, "num must be a positive integer");
+
+        //functional for loop
+        (1..=num)
+            .map(|i| Task::new(false, format!("Mock Task {}", i)))
+            .collect() 
+    }
+
+    #[test]
+    fn test_createmocklist(){
+        let num = 5;
+        let mock_tasks = create_mocklist(num);
+
+        assert_eq!(num, mock_tasks.len() as i32);
+    }
+
+    #[test]
+    fn test_dependencies(){
+        let mut task_list=TaskList{
+            tasks:create_mocklist(3)
+        };
+        task_list.ensure_ids();
+
+        // task 3 waits on task 1, task 1 waits on task 2
+        assert!(task_list.add_dependency(2,0).is_ok());
+        assert!(task_list.add_dependency(0,1).is_ok());
+        assert!(task_list.is_blocked(2));
+        assert_eq!(task_list.actionable_tasks(),vec![1]);
+
+        // closing the loop must be rejected
+        assert!(task_list.add_dependency(1,2).is_err());
+        assert!(task_list.add_dependency(1,1).is_err());
+
+        // links survive a round trip and follow ids, not positions
+        let reloaded=convert_stringtotl(convert_tltostring(task_list.clone()));
+        assert_eq!(reloaded.tasks[2].depends_on,vec![reloaded.tasks[0].id]);
+
+        let _=task_list.toggle_completed_task(1);
+        let _=task_list.delete_task(0);
+        assert!(!task_list.is_blocked(1));
+        assert!(task_list.tasks[1].depends_on.is_empty());
+    }
+
+    #[test]
+    fn test_recurrence(){
+        use crate::rusty_recurrence::Recurrence;
+        use chrono::Weekday;
+
+        let weekly:Recurrence="weekly fri,mon".parse().unwrap();
+        assert_eq!(weekly,Recurrence::Weekly(vec![Weekday::Mon,Weekday::Fri]));
+        assert_eq!(weekly.to_string().parse::<Recurrence>().unwrap(),weekly);
+        assert!("monthly 40".parse::<Recurrence>().is_err());
+
+        // Friday 2024-03-01 due, completed on time
+        let due=Local.with_ymd_and_hms(2024,3,1,9,0,0).unwrap().to_utc();
+        let on_time=Local.with_ymd_and_hms(2024,3,1,8,0,0).unwrap().to_utc();
+        let next=weekly.next_due(Some(due),on_time).with_timezone(&Local);
+        assert_eq!(next.date_naive(),NaiveDate::from_ymd_opt(2024,3,4).unwrap());
+
+        // monthly on the 31st clamps to the end of April
+        let march=Local.with_ymd_and_hms(2024,3,31,9,0,0).unwrap().to_utc();
+        let next=Recurrence::Monthly(31).next_due(Some(march),march).with_timezone(&Local);
+        assert_eq!(next.date_naive(),NaiveDate::from_ymd_opt(2024,4,30).unwrap());
+
+        // daily tasks finished late come back after the completion, not in the past
+        let late=Local.with_ymd_and_hms(2024,3,5,12,0,0).unwrap().to_utc();
+        let next=Recurrence::Daily.next_due(Some(due),late).with_timezone(&Local);
+        assert_eq!(next.date_naive(),NaiveDate::from_ymd_opt(2024,3,6).unwrap());
+
+        let next=Recurrence::AfterCompletion(3).next_due(Some(due),late).with_timezone(&Local);
+        assert_eq!(next.date_naive(),NaiveDate::from_ymd_opt(2024,3,8).unwrap());
+
+        // completing a recurring task logs it and adds the next instance
+        let mut task_list=TaskList{
+            tasks:create_mocklist(1)
+        };
+        task_list.tasks[0].recurrence=Some(Recurrence::Daily);
+        task_list.tasks[0].due_date=Some(due);
+        let _=task_list.toggle_completed_task(0);
+        let next_index=task_list.spawn_next_recurrence(0).unwrap();
+        assert!(task_list.tasks[0].recurrence.is_none());
+        assert!(!task_list.tasks[next_index].completed);
+        assert_eq!(task_list.tasks[next_index].recurrence,Some(Recurrence::Daily));
+        let reloaded=convert_stringtotl(convert_tltostring(task_list.clone()));
+        assert_eq!(reloaded.tasks[next_index].recurrence,Some(Recurrence::Daily));
+    }
+
+    #[test]
+    fn test_notes(){
+        let mut task_list=TaskList{
+            tasks:create_mocklist(2)
+        };
+        task_list.tasks[0].notes=vec![
+            "https://example.com/ticket/1".to_string(),
+            "".to_string(),
+            " - [ ] [Due: ] [Completed: ] looks like a task".to_string()
+        ];
+        let reloaded=convert_stringtotl(convert_tltostring(task_list.clone()));
+        assert_eq!(reloaded.tasks.len(),2);
+        assert_eq!(reloaded.tasks[0].notes,task_list.tasks[0].notes);
+        assert!(reloaded.tasks[1].notes.is_empty());
+    }
+
+    #[test]
+    fn test_editorroundtrip(){
+        use crate::rusty_editor::summarize_changes;
+
+        let mut task_list=TaskList{
+            tasks:create_mocklist(3)
+        };
+        task_list.ensure_ids();
+        let _=task_list.toggle_completed_task(0);
+        task_list.tasks[0].completed_date=Some(Utc::now());
+
+        // an untouched round trip is clean and reports no changes
+        let text=convert_tltostring(task_list.clone());
+        assert!(find_parse_problems(&text).is_empty());
+        let reloaded=convert_stringtotl(text.clone());
+        assert!(summarize_changes(&task_list.tasks,&reloaded.tasks).is_empty());
+
+        // editors may save with plain newlines and mangle lines
+        let edited=text.replace("\r\n","\n")
+                       .replace("Mock Task 2","Mock Task 2 edited")
+                       .replace(" - [ ] [Due: ] [Completed: ] [Id: 3]","- broken");
+        assert_eq!(find_parse_problems(&edited).len(),1);
+        let changes=summarize_changes(&task_list.tasks,&convert_stringtotl(edited).tasks);
+        assert_eq!(changes,vec!["~ changed: Mock Task 2 edited","- removed: Mock Task 3"]);
+
+        // editing one task is checked against the rest of the list
+        use crate::rusty_commands::check_edited_text;
+        assert!(task_list.add_dependency(0,1).is_ok());
+        let one=convert_tltostring(TaskList{ tasks:vec![task_list.tasks[1].clone()] });
+        assert!(check_edited_text(&task_list,Some(1),&one.replace("Mock Task 2","Mock Task 2 edited")).is_ok());
+        for deps in ["[Deps: 1]","[Deps: 2]","[Deps: 9]"] {
+            let edited=one.replace("[Id: 2]",&format!("[Id: 2] {deps}"));
+            assert!(check_edited_text(&task_list,Some(1),&edited).is_err(),"{deps}");
+        }
+    }
+
+    #[test]
+    fn test_timestamps(){
+        let mut task_list=TaskList{
+            tasks:Vec::new()
+        };
+        let _=task_list.add_task(Task::new(false,"b".to_string()));
+        let _=task_list.add_task(Task::new(false,"a".to_string()));
+        let created=task_list.tasks[0].created_at.unwrap();
+        assert_eq!(task_list.tasks[0].updated_at,Some(created));
+
+        let _=task_list.toggle_completed_task(0);
+        assert!(task_list.tasks[0].updated_at.unwrap() >= created);
+        assert_eq!(task_list.tasks[0].created_at,Some(created));
+
+        // timestamps round trip, old lines without them get backfilled
+        let mut reloaded=convert_stringtotl(convert_tltostring(task_list.clone()));
+        assert_eq!(reloaded.tasks[0].created_at,task_list.tasks[0].created_at);
+        assert_eq!(reloaded.tasks[0].updated_at,task_list.tasks[0].updated_at);
+        let mut old=convert_stringtotl("# TaskList:\r\n - [ ] [Due: ] [Completed: ] old\r\n".to_string());
+        let file_time=Utc::now()-chrono::TimeDelta::try_days(10).unwrap();
+        old.backfill_timestamps(file_time);
+        assert_eq!(old.tasks[0].created_at,Some(file_time));
+        assert_eq!(old.tasks[0].updated_at,Some(file_time));
+
+        // sorts and filters can use them
+        reloaded.sort_by_field("text",false).unwrap();
+        assert_eq!(reloaded.tasks[0].data,"a");
+        assert!(reloaded.sort_by_field("updated",true).is_ok());
+        assert!(reloaded.sort_by_field("colour",true).is_err());
+        let stale=old.filter_by_date("updated",true,Utc::now()-chrono::TimeDelta::try_days(7).unwrap()).unwrap();
+        assert_eq!(stale,vec![0]);
+    }
+
+    #[test]
+    fn test_agenda(){
+        use crate::rusty_agenda::*;
+
+        // Wednesday; dates are compared as local calendar days
+        let today=NaiveDate::from_ymd_opt(2024,3,13).unwrap();
+        let at=|d:u32,h:u32| Some(Local.with_ymd_and_hms(2024,3,d,h,0,0).unwrap().to_utc());
+        assert_eq!(AgendaBucket::for_due_date(at(12,23),today),AgendaBucket::Overdue);
+        assert_eq!(AgendaBucket::for_due_date(at(13,0),today),AgendaBucket::Today);
+        assert_eq!(AgendaBucket::for_due_date(at(13,23),today),AgendaBucket::Today);
+        assert_eq!(AgendaBucket::for_due_date(at(14,1),today),AgendaBucket::Tomorrow);
+        assert_eq!(AgendaBucket::for_due_date(at(17,9),today),AgendaBucket::ThisWeek);
+        assert_eq!(AgendaBucket::for_due_date(at(18,9),today),AgendaBucket::Later);
+        assert_eq!(AgendaBucket::for_due_date(None,today),AgendaBucket::NoDate);
+
+        let mut task_list=TaskList{
+            tasks:create_mocklist(4)
+        };
+        task_list.tasks[0].due_date=at(20,9);
+        task_list.tasks[1].due_date=at(1,9);
+        task_list.tasks[2].due_date=at(15,9);
+        task_list.tasks[3].due_date=at(2,9);
+        let _=task_list.toggle_completed_task(3);
+        let groups=group_tasks(&task_list,today);
+        assert_eq!(groups[0],(AgendaBucket::Overdue,vec![1]));
+        assert_eq!(groups[3],(AgendaBucket::ThisWeek,vec![2]));
+        assert_eq!(groups[4],(AgendaBucket::Later,vec![0]));
+        assert_eq!(upcoming_tasks(&task_list,today,7),vec![2,0]);
+    }
+
+    #[test]
+    fn test_calendar(){
+        use crate::rusty_calendar::*;
+        use chrono::Weekday;
+
+        let mut task_list=TaskList{
+            tasks:create_mocklist(3)
+        };
+        let due=Some(Local.with_ymd_and_hms(2024,3,15,9,0,0).unwrap().to_utc());
+        task_list.tasks[0].due_date=due;
+        task_list.tasks[1].due_date=due;
+        let march=NaiveDate::from_ymd_opt(2024,3,1).unwrap();
+        assert_eq!(tasks_on_day(&task_list,NaiveDate::from_ymd_opt(2024,3,15).unwrap()),vec![0,1]);
+
+        // March 2024 starts on a Friday
+        let monday=strip_ansi(&render_month(&task_list,march,march,Weekday::Mon));
+        let lines:Vec<&str>=monday.lines().collect();
+        assert_eq!(lines[1].trim(),"Mo    Tu    We    Th    Fr    Sa    Su");
+        assert!(lines[2].trim_end().ends_with("  1     2     3"));
+        assert!(lines[4].contains(" 15*2"));
+        let sunday=strip_ansi(&render_month(&task_list,march,march,Weekday::Sun));
+        assert!(sunday.lines().nth(1).unwrap().trim().starts_with("Su"));
+        assert!(sunday.lines().nth(2).unwrap().trim_end().ends_with("  1     2"));
+    }
+
+    #[test]
+    fn test_tuistate(){
+        use crate::rusty_tui::*;
+
+        let mut task_list=TaskList{
+            tasks:create_mocklist(12)
+        };
+        task_list.tasks[3].notes.push("mentions Widget".to_string());
+        assert_eq!(visible_tasks(&task_list,"").len(),12);
+        assert_eq!(visible_tasks(&task_list,"task 1"),vec![0,9,10,11]);
+        assert_eq!(visible_tasks(&task_list,"widget"),vec![3]);
+
+        let mut state=TuiState::default();
+        state.move_selection(-1,12);
+        assert_eq!(state.selected,0);
+        state.move_selection(20,12);
+        assert_eq!(state.selected,11);
+        state.scroll_into_view(5);
+        assert_eq!(state.offset,7);
+        state.move_selection(-10,12);
+        state.scroll_into_view(5);
+        assert_eq!(state.offset,1);
+        state.move_selection(0,0);
+        assert_eq!(state.selected,0);
+
+        // warnings end up in the status line instead of on the raw terminal
+        let mut store=crate::rusty_store::MemoryStore::default();
+        task_list.ensure_ids();
+        for task in task_list.tasks.iter_mut(){
+            task.completed=false;
+        }
+        assert!(task_list.add_dependency(0,1).is_ok());
+        state.report(command_complete(&mut task_list,1,&mut store),"");
+        assert_eq!(state.message,"Warning: task 1 still has open prerequisites: 2");
+        state.report(command_remove(&mut task_list,1,&mut store),"Task removed.");
+        assert_eq!(state.message,"Task removed.");
+        state.report(command_remove(&mut task_list,99,&mut store),"Task removed.");
+        assert_eq!(state.message,"Invalid REMOVE command please try again.");
+
+        // rows are drawn in the theme's colours
+        assert_eq!(term_color(colored::Color::TrueColor{ r:125, g:125, b:125 }),crossterm::style::Color::Rgb{ r:125, g:125, b:125 });
+        assert_eq!(term_color(colored::Color::BrightBlack),crossterm::style::Color::DarkGrey);
+    }
+
+    #[test]
+    fn test_completion(){
+        use crate::rusty_readline::TaskHelper;
+
+        let mut task_list=TaskList{
+            tasks:create_mocklist(12)
+        };
+        task_list.tasks[0].data="Ship it #release +web @office".to_string();
+        task_list.tasks[1].data="Plan #retro, then #release.".to_string();
+        let mut helper=TaskHelper::new();
+        helper.update(&task_list);
+
+        assert_eq!(helper.candidates("comp"),(0,vec!["complete".to_string()]));
+        assert_eq!(helper.candidates("help re"),(5,vec!["remove".to_string(),"recur".to_string(),"rename-list".to_string()]));
+        assert_eq!(helper.candidates("remove 1").1,vec!["1","10","11","12"]);
+        assert_eq!(helper.candidates("depend 3,1").0,9);
+        assert_eq!(helper.candidates("add Pick up #re").1,vec!["#release","#retro"]);
+        assert_eq!(helper.candidates("add +").1,vec!["+web"]);
+        assert!(helper.candidates("add 1").1.is_empty());
+    }
+
+    #[test]
+    fn test_tokenizer(){
+        use crate::rusty_args::tokenize_arguments;
+
+        let args=tokenize_arguments("Buy eggs, milk, bread due:2024-03-30");
+        assert_eq!(args.positional,vec!["Buy eggs","milk","bread"]);
+        assert_eq!(args.get("due").map(|d| d.as_str()),Some("2024-03-30"));
+
+        let args=tokenize_arguments(r#""Read re:invoice, today" due:"2024-03-30 12:00:00 -05:00" recur:daily"#);
+        assert_eq!(args.text(),"Read re:invoice, today");
+        assert_eq!(args.get("due").map(|d| d.as_str()),Some("2024-03-30 12:00:00 -05:00"));
+        assert_eq!(args.get("recur").map(|d| d.as_str()),Some("daily"));
+
+        let args=tokenize_arguments(r#"Say \"hi\" at 10:30 "due:soon" due\:later"#);
+        assert_eq!(args.text(),r#"Say "hi" at 10:30 due:soon due:later"#);
+        assert!(args.named.is_empty());
+
+        let args=tokenize_arguments("updated --desc");
+        assert_eq!(args.positional,vec!["updated"]);
+        assert!(args.has_flag("desc"));
+        assert_eq!(tokenize_arguments("").positional,vec![""]);
+        assert_eq!(tokenize_arguments("a,").positional,vec!["a",""]);
+
+        // apostrophes and backslashes in the middle of words are just text
+        assert_eq!(tokenize_arguments("Don't forget milk").text(),"Don't forget milk");
+        assert_eq!(tokenize_arguments(r"see C:\data\work.md").text(),r"see C:\data\work.md");
+        assert_eq!(tokenize_arguments(r#"'quoted text' and "never closed"#).text(),r#"quoted text and "never closed"#);
+        assert_eq!(tokenize_arguments(r"load C:\data\work.md").raw(),r"load C:\data\work.md");
+
+        // text keeps the commas and spacing it was typed with
+        let args=tokenize_arguments("3,a,b  c, d --force");
+        assert_eq!(args.text(),"3,a,b  c, d");
+        assert_eq!(args.text_after_first(),"a,b  c, d");
+        assert_eq!(tokenize_arguments("3").text_after_first(),"");
+
+        // the deprecated 'add text,date' form still sets a due date
+        let mut task_list=TaskList{ tasks:Vec::new() };
+        let mut store:Box<dyn TaskStore>=Box::new(crate::rusty_store::MemoryStore::default());
+        let (command,args)=parse_input_commands("add Testing,2024-03-30");
+        let _=handle_command(resolve_command(&command).unwrap(),args,&mut task_list,store.as_mut());
+        let (command,args)=parse_input_commands("add Buy eggs, milk due:2024-03-31 recur:daily");
+        let _=handle_command(resolve_command(&command).unwrap(),args,&mut task_list,store.as_mut());
+        assert_eq!(task_list.tasks[0].data,"Testing");
+        assert!(task_list.tasks[0].due_date.is_some());
+        assert_eq!(task_list.tasks[1].data,"Buy eggs, milk");
+        assert!(task_list.tasks[1].recurrence.is_some());
+
+        for line in ["note 2,Call Ann,Bob","edit 1, Pay rent,deposit","add Pack a,b"] {
+            let (command,args)=parse_input_commands(line);
+            let _=handle_command(resolve_command(&command).unwrap(),args,&mut task_list,store.as_mut());
+        }
+        assert_eq!(task_list.tasks[1].notes,vec!["Call Ann,Bob"]);
+        assert_eq!(task_list.tasks[0].data,"Pay rent,deposit");
+        assert_eq!(task_list.tasks[2].data,"Pack a,b");
+
+        // a bad recurrence rule stops the add
+        let (command,args)=parse_input_commands("add Water plants recur:fortnightly");
+        assert!(handle_command(resolve_command(&command).unwrap(),args,&mut task_list,store.as_mut()).is_err());
+        assert_eq!(task_list.tasks.len(),3);
+    }
+
+    #[test]
+    fn test_aliases(){
+        use crate::rusty_config::parse_config;
+
+        assert!(matches!(resolve_command("comp"),Ok(TASKCOM::Complete)));
+        assert!(matches!(resolve_command("LIST"),Ok(TASKCOM::List)));
+        assert!(matches!(resolve_command("edit"),Ok(TASKCOM::Edit)));
+        assert!(resolve_command("re").unwrap_err().contains("remove, recur"));
+        assert!(resolve_command("compelte").unwrap_err().contains("did you mean 'complete'?"));
+        assert!(!resolve_command("xyzzy").unwrap_err().contains("did you mean"));
+        assert_eq!(edit_distance("kitten","sitting"),3);
+
+        let config=parse_config("[aliases]\nt = \"today\"\nhome = \"filter due,before,7d\"\n").unwrap();
+        assert_eq!(expand_alias("t",&config.aliases),"today");
+        assert_eq!(expand_alias("HOME extra",&config.aliases),"filter due,before,7d extra");
+        assert_eq!(expand_alias("rm 2",&config.aliases),"remove 2");
+        assert_eq!(expand_alias("ls",&config.aliases),"list");
+        assert_eq!(expand_alias("today",&config.aliases),"today");
+        assert!(parse_config("[aliases]\n\"two words\" = \"list\"\n").is_err());
+        assert!(parse_config("[aliases]\nx = 3\n").is_err());
+
+        // alias names are not case sensitive and can not hide a command
+        let config=parse_config("[aliases]\nTD = \"today\"\n").unwrap();
+        assert_eq!(expand_alias("td",&config.aliases),"today");
+        assert!(parse_config("[aliases]\nList = \"today\"\n").unwrap_err().contains("LIST"));
+        assert!(parse_config("[aliases]\nT = \"today\"\nt = \"list\"\n").is_err());
+    }
+
+    #[test]
+    fn test_script(){
+        use crate::rusty_store::store_for;
+
+        let datafilepath="testdata/script.md".to_string();
+        let _=std::fs::remove_file(&datafilepath);
+
+        // every command runs but the file is written once, at the end
+        let script="# sprint setup\nadd Plan sprint\n\nadd Review PRs due:2024-03-30\ncomplete 1\nexit\nadd never runs\n";
+        let task_list=&mut TaskList{ tasks:Vec::new() };
+        assert!(run_script(io::Cursor::new(script),task_list,store_for(&datafilepath).unwrap().as_mut(),false));
+        let saved=load_tlfromfile(datafilepath.clone());
+        assert_eq!(saved.tasks.len(),2);
+        assert!(saved.tasks[0].completed);
+
+        // a failing line stops the script and nothing is saved
+        let stoppedfilepath="testdata/script_stopped.md".to_string();
+        let _=std::fs::remove_file(&stoppedfilepath);
+        let task_list=&mut TaskList{ tasks:Vec::new() };
+        assert!(!run_script(io::Cursor::new("add Third\ncompelte 1\nadd Fourth\n"),task_list,store_for(&stoppedfilepath).unwrap().as_mut(),false));
+        assert_eq!(task_list.tasks.len(),1);
+        assert!(!std::path::Path::new(&stoppedfilepath).exists());
+
+        // unless it is told to keep going
+        let task_list=&mut TaskList{ tasks:Vec::new() };
+        assert!(!run_script(io::Cursor::new("add Third\ncompelte 1\nadd Fourth\n"),task_list,store_for(&stoppedfilepath).unwrap().as_mut(),true));
+        assert_eq!(load_tlfromfile(stoppedfilepath.clone()).tasks.len(),2);
+
+        // deleting a list is refused, a save held back for the end could not follow it
+        let _=std::fs::remove_file(&stoppedfilepath);
+        let task_list=&mut TaskList{ tasks:Vec::new() };
+        assert!(!run_script(io::Cursor::new("add Fifth\ndelete-list old --force\n"),task_list,store_for(&stoppedfilepath).unwrap().as_mut(),false));
+        assert!(!std::path::Path::new(&stoppedfilepath).exists());
+    }
+
+    #[test]
+    fn test_config(){
+        use crate::rusty_config::*;
+
+        let mut config=parse_config("indent = 2\n[colors]\nlabel = \"bright blue\"\n").unwrap();
+        assert_eq!(config.indent,2);
+        assert_eq!(config.data_file,"data/tasklist.md");
+        assert_eq!(config.get("colors.label").unwrap(),"bright blue");
+        assert!(parse_config("indnet = 2\n").is_err());
+        assert!(parse_config("week_start = \"friday\"\n").is_err());
+        assert!(parse_config("date_format = \"%Y-%Q\"\n").is_err());
+        assert!(parse_config("storage_date_format = \"%Y-%m-%d\"\n").is_err());
+        assert!(parse_config("storage_date_format = \"%d.%m.%Y %H:%M:%S%.f %z\"\n").is_ok());
+
+        assert!(parse_config("[colors]\nmuted = \"300,0,0\"\n").is_err());
+
+        config.set("indent","6").unwrap();
+        config.set("colors.muted","90, 90, 90").unwrap();
+        config.set("aliases.t","today").unwrap();
+        assert_eq!(config.indent,6);
+        assert_eq!(config.aliases["t"],"today");
+        assert!(config.set("indent","lots").is_err());
+        assert!(config.set("edit_mode","nano").is_err());
+        assert!(config.set("nothing.here","1").is_err());
+        assert_eq!(config.indent,6);
+
+        // a saved config loads back the same
+        let configfilepath=std::path::PathBuf::from("testdata/config.toml");
+        config.path=Some(configfilepath.clone());
+        config.save().unwrap();
+        assert_eq!(load_config(Some(configfilepath)).unwrap(),config);
+        assert!(load_config(Some("testdata/missing.toml".into())).is_err());
+    }
+
+    #[test]
+    fn test_themes(){
+        use crate::rusty_config::*;
+        use crate::rusty_theme::*;
+        use colored::Color;
+
+        let config=parse_config("theme = \"light\"\n[colors]\nlabel = \"cyan\"\n").unwrap();
+        let palette=Palette::from_config(&config);
+        assert_eq!(palette.label,Color::Cyan);
+        assert_eq!(palette.text,Color::Black);
+        assert_eq!(config.get("colors.text").unwrap(),"black");
+        assert_eq!(Palette::from_config(&Config::default()).muted,Color::TrueColor{ r:125, g:125, b:125 });
+
+        // user themes start from the dark theme
+        let config=parse_config("theme = \"paper\"\n[themes.paper]\ndue = \"blue\"\n").unwrap();
+        assert_eq!(Palette::from_config(&config).due,Color::Blue);
+        assert_eq!(Palette::from_config(&config).label,Color::Magenta);
+        assert_eq!(theme_names(&config),vec!["dark","light","paper"]);
+
+        assert!(parse_config("theme = \"neon\"\n").unwrap_err().contains("dark, light"));
+        assert!(parse_config("[themes.paper]\ndue = \"blurple\"\n").unwrap_err().contains("themes.paper.due"));
+        assert!(parse_config("color = \"sometimes\"\n").is_err());
+        assert!(parse_config("unicode = \"never\"\ncolor = \"never\"\n").is_ok());
+    }
+
+    #[test]
+    fn test_table(){
+        use crate::rusty_table::*;
+
+        assert_eq!(truncate("short",10),"short");
+        assert_eq!(truncate("a longer text",8).chars().count(),8);
+        assert_eq!(wrap("one two three four",9),vec!["one two","three","four"]);
+        assert_eq!(wrap("abcdefghij",4),vec!["abcd","efgh","ij"]);
+        let today=NaiveDate::from_ymd_opt(2024,3,10).unwrap();
+        let on=|day:u32| Local.from_local_datetime(&NaiveDate::from_ymd_opt(2024,3,day).unwrap().and_hms_opt(9,0,0).unwrap()).unwrap().with_timezone(&Utc);
+        assert_eq!(relative_date(on(12),today),"in 2d");
+        assert_eq!(relative_date(on(7),today),"3d ago");
+        assert_eq!(relative_date(on(10),today),"today");
+        assert!(parse_columns("due,txt").is_err());
+
+        let mut task_list=TaskList{ tasks:create_mocklist(3) };
+        task_list.tasks[0].data="A task with a long description that needs more room".to_string();
+        for task in task_list.tasks.iter_mut(){
+            task.completed=false;
+            task.due_date=None;
+            task.completed_date=None;
+        }
+        let options=ListOptions{
+            columns:parse_columns("number,status,due,text").unwrap(),
+            relative_dates:false,
+            wrap:false,
+            width:30,
+            limit:None,
+            offset:0
+        };
+        // the empty due column is left out and every line fits the width
+        let table=TaskTable::new(&task_list,&[0,1,2],&options);
+        assert_eq!(strip_ansi(&table.header()),"#       Task");
+        let row:Vec<String>=table.rows(&task_list,0).iter().map(|line| strip_ansi(line)).collect();
+        assert_eq!(row.len(),1);
+        assert!(row[0].starts_with("1  [ ]  A task"));
+        assert!(row[0].chars().count()+4 <= 30);
+
+        let wrapped=TaskTable::new(&task_list,&[0],&ListOptions{ wrap:true, ..options });
+        let lines:Vec<String>=wrapped.rows(&task_list,0).iter().map(|line| strip_ansi(line)).collect();
+        assert!(lines.len() > 1);
+        assert!(lines[1].starts_with("        "));
+    }
+
+    #[test]
+    fn test_paging(){
+        use crate::rusty_pager::split_pages;
+        use crate::rusty_table::ListOptions;
+        use crate::rusty_args::tokenize_arguments;
+
+        let text=(1..=10).map(|n| n.to_string()).collect::<Vec<_>>().join("\n");
+        let pages=split_pages(&text,5);
+        assert_eq!(pages.len(),3);
+        assert_eq!(pages[0],"1\n2\n3\n4");
+        assert_eq!(pages[2],"9\n10");
+
+        let task_list=TaskList{ tasks:create_mocklist(30) };
+        let all:Vec<usize>=(0..30).collect();
+        let options=ListOptions::from_args(&tokenize_arguments("--limit=10 --offset=5")).unwrap();
+        assert_eq!((options.limit,options.offset),(Some(10),5));
+        let shown=strip_ansi(&task_list.render_table("Tasks: ",&all,&options));
+        assert!(shown.contains("Showing 6-15 of 30, --offset=15 shows the next."));
+        assert!(!shown.contains("\n     5  "));
+        assert!(shown.contains("\n     6  "));
+        let past_end=ListOptions{ offset:40, ..options };
+        assert!(strip_ansi(&task_list.render_table("Tasks: ",&all,&past_end)).contains("Nothing after --offset=40"));
+        assert!(ListOptions::from_args(&tokenize_arguments("--limit=lots")).is_err());
+    }
+
+    #[test]
+    fn test_todotxt(){
+        use crate::rusty_todotxt::*;
+        use crate::rusty_args::tokenize_arguments;
+        use crate::rusty_store::MemoryStore;
+
+        let task=parse_todotxt_line("(A) 2024-02-20 Call mum +family @phone due:2024-03-05").unwrap();
+        assert_eq!(task.priority,Some('A'));
+        assert_eq!(task.data,"Call mum +family @phone");
+        assert_eq!(task.tags(),vec!["+family","@phone"]);
+        assert_eq!(task.due_date.unwrap().with_timezone(&Local).date_naive(),NaiveDate::from_ymd_opt(2024,3,5).unwrap());
+        let done=parse_todotxt_line("x 2024-03-01 2024-02-20 Pay rent pri:B").unwrap();
+        assert!(done.completed);
+        assert_eq!(done.priority,Some('B'));
+        assert_eq!(done.completed_date.unwrap().with_timezone(&Local).date_naive(),NaiveDate::from_ymd_opt(2024,3,1).unwrap());
+        assert!(parse_todotxt_line("   ").is_none());
+
+        // what goes out comes back the same
+        let data="(A) 2024-02-20 Call mum +family @phone due:2024-03-05\nx 2024-03-01 2024-02-20 Pay rent pri:B\nWater plants\n";
+        let task_list=convert_todotxttotl(data);
+        assert_eq!(task_list.tasks.len(),3);
+        assert_eq!(convert_tltotodotxt(&task_list),data);
+        let reloaded=convert_stringtotl(convert_tltostring(task_list.clone()));
+        assert_eq!(convert_tltotodotxt(&reloaded),data);
+
+        let (format,path)=exchange_arguments(&tokenize_arguments("--format todotxt data/out"));
+        assert_eq!((format.as_deref(),path.as_str()),(Some("todotxt"),"data/out"));
+        let (format,path)=exchange_arguments(&tokenize_arguments(r#"--format=csv "C:\data\Bob's tasks.csv""#));
+        assert_eq!((format.as_deref(),path.as_str()),(Some("csv"),r"C:\data\Bob's tasks.csv"));
+        assert!(matches!(ExchangeFormat::from_name("IMPORT",None,"todo.txt"),Ok(ExchangeFormat::TodoTxt)));
+        let mut imported=TaskList{ tasks:create_mocklist(2) };
+        let _ = std::fs::create_dir_all("testdata");
+        std::fs::write("testdata/import.txt",data).unwrap();
+        let indexes=command_import(&mut imported,Some("txt".to_string()),"testdata/import.txt".to_string(),false,&mut MemoryStore::default()).unwrap();
+        assert_eq!(indexes,vec![2,3,4]);
+        assert_eq!(imported.tasks[2].created_at,task_list.tasks[0].created_at);
+        assert!(command_export(&imported,Some("pdf".to_string()),String::new()).is_err());
+
+        // exports go anywhere and replace what is there, not only files under ./data
+        let _ = std::fs::remove_file("testdata/export.txt");
+        command_export(&imported,None,"testdata/export.txt".to_string()).unwrap();
+        command_export(&task_list,None,"testdata/export.txt".to_string()).unwrap();
+        assert_eq!(std::fs::read_to_string("testdata/export.txt").unwrap(),data);
+    }
+
+    #[test]
+    fn test_ical(){
+        use crate::rusty_ical::*;
+        use crate::rusty_serializer::{JsonSerializer, TaskSerializer, YamlSerializer};
+        use crate::rusty_store::MemoryStore;
+
+        let mut task_list=TaskList{ tasks:create_mocklist(3) };
+        task_list.tasks[0].data="Buy milk, eggs; and a very long list of other things that needs folding over lines".to_string();
+        task_list.tasks[0].notes=vec!["first".to_string(),"second".to_string()];
+        task_list.tasks[0].priority=Some('B');
+        task_list.tasks[1].due_date=Some(Utc.with_ymd_and_hms(2024,3,5,14,30,0).unwrap());
+        task_list.tasks[2].completed=true;
+        task_list.tasks[2].completed_date=Some(Utc.with_ymd_and_hms(2024,3,1,8,0,0).unwrap());
+        let data=convert_tltoical(&task_list);
+        assert!(data.lines().all(|line| line.len() <= 76));
+        assert!(data.contains(&format!("UID:{}",task_uid(&task_list.tasks[1]))));
+        assert!(data.contains("DUE:20240305T143000Z"));
+        assert!(data.contains("STATUS:COMPLETED\r\nCOMPLETED:20240301T080000Z"));
+        // a priority that is not a letter is refused when a JSON or YAML list is read, and never breaks an export
+        let json="{\"tasks\":[{\"id\":1,\"data\":\"Odd\",\"priority\":\"1\"}]}";
+        assert!(JsonSerializer.deserialize(json).unwrap_err().contains("priority '1' is not a letter"));
+        assert_eq!(YamlSerializer.deserialize("tasks:\n- data: Low\n  priority: c\n").unwrap().tasks[0].priority,Some('C'));
+        let mut odd=TaskList{ tasks:create_mocklist(1) };
+        odd.tasks[0].priority=Some('1');
+        assert!(!convert_tltoical(&odd).contains("PRIORITY"));
+
+        let reloaded=convert_icaltotl(&data).unwrap();
+        assert_eq!(reloaded.tasks.len(),3);
+        for (before,after) in task_list.tasks.iter().zip(&reloaded.tasks){
+            assert_eq!((&before.data,&before.notes,before.priority),(&after.data,&after.notes,after.priority));
+            assert_eq!((before.completed,before.due_date),(after.completed,after.due_date));
+            assert_eq!(before.created_at.map(|d| d.timestamp()),after.created_at.map(|d| d.timestamp()));
+        }
+        assert_eq!(reloaded.tasks[2].completed_date,task_list.tasks[2].completed_date);
+
+        // days, zones and floating times from other calendar clients
+        let other="BEGIN:VCALENDAR\nBEGIN:VEVENT\nSUMMARY:not a task\nEND:VEVENT\nBEGIN:VTODO\nUID:zoned@example.com\nSUMMARY:Zoned\nDUE;TZID=America/New_York:20240305T090000\n\
+            BEGIN:VALARM\nDESCRIPTION:alarm\nEND:VALARM\nEND:VTODO\nBEGIN:VTODO\nUID:whole@example.com\nSUMMARY:Whole\n day\nDUE;VALUE=DATE:20240306\nEND:VTODO\nEND:VCALENDAR\n";
+        let imported=convert_icaltotl(other).unwrap();
+        assert_eq!(imported.tasks.len(),2);
+        assert_eq!(imported.tasks[0].uid.as_deref(),Some("zoned@example.com"));
+        assert!(convert_tltoical(&imported).contains("UID:zoned@example.com"));
+        assert_eq!(convert_stringtotl(convert_tltostring(imported.clone())).tasks[0].uid,imported.tasks[0].uid);
+        assert_eq!(imported.tasks[0].due_date,Some(Utc.with_ymd_and_hms(2024,3,5,14,0,0).unwrap()));
+        assert!(imported.tasks[0].notes.is_empty());
+        assert_eq!(imported.tasks[1].data,"Wholeday");
+        assert_eq!(imported.tasks[1].due_date.unwrap().with_timezone(&Local).date_naive(),NaiveDate::from_ymd_opt(2024,3,6).unwrap());
+        assert!(convert_icaltotl("BEGIN:VTODO\nDUE:tomorrow\nEND:VTODO\n").unwrap_err().starts_with("Line 2:"));
+
+        // a summary over several lines is joined, a task is one line of the Markdown file
+        let multiline=convert_icaltotl("BEGIN:VTODO\nSUMMARY:Pack\\nthe car\nEND:VTODO\n").unwrap();
+        assert_eq!(multiline.tasks[0].data,"Pack the car");
+
+        // importing the calendar again updates the tasks with the same UID instead of adding them
+        let _ = std::fs::create_dir_all("testdata");
+        std::fs::write("testdata/calendar.ics",other).unwrap();
+        let mut synced=TaskList{ tasks:Vec::new() };
+        command_import(&mut synced,None,"testdata/calendar.ics".to_string(),false,&mut MemoryStore::default()).unwrap();
+        std::fs::write("testdata/calendar.ics",other.replace("SUMMARY:Zoned","SUMMARY:Zoned call")).unwrap();
+        let indexes=command_import(&mut synced,None,"testdata/calendar.ics".to_string(),false,&mut MemoryStore::default()).unwrap();
+        assert_eq!(indexes,vec![0,1]);
+        assert_eq!(synced.tasks.len(),2);
+        assert_eq!(synced.tasks[0].data,"Zoned call");
+
+        // and so does an export of this list
+        let mut exported=TaskList{ tasks:create_mocklist(2) };
+        exported.ensure_ids();
+        std::fs::write("testdata/export.ics",convert_tltoical(&exported).replace("STATUS:NEEDS-ACTION","STATUS:COMPLETED")).unwrap();
+        command_import(&mut exported,None,"testdata/export.ics".to_string(),false,&mut MemoryStore::default()).unwrap();
+        assert_eq!(exported.tasks.len(),2);
+        assert!(exported.tasks.iter().all(|task| task.completed));
+        assert!(matches!(ExchangeFormat::from_name("EXPORT",Some("ics".to_string()),""),Ok(ExchangeFormat::ICal)));
+    }
+
+    #[test]
+    fn test_csv(){
+        use crate::rusty_csv::*;
+        use crate::rusty_store::MemoryStore;
+
+        let mut task_list=TaskList{ tasks:create_mocklist(3) };
+        task_list.ensure_ids();
+        task_list.tasks[0].data="Say \"hi\", then leave".to_string();
+        task_list.tasks[0].notes=vec!["line one".to_string(),"line, two".to_string()];
+        task_list.tasks[1].due_date=Some(parse_due_date("2024-03-05").unwrap());
+        task_list.tasks[1].recurrence=Some("weekly mon,fri".parse().unwrap());
+        task_list.tasks[1].priority=Some('C');
+        task_list.tasks[1].uid=Some("cal-42@example.com".to_string());
+        let first_id=task_list.tasks[0].id;
+        task_list.tasks[2].depends_on=vec![first_id];
+        let data=convert_tltocsv(&task_list);
+        assert!(data.contains("\"Say \"\"hi\"\", then leave\""));
+        assert!(data.contains(",2024-03-05,"));
+        let (reloaded,problems)=convert_csvtotl(&data).unwrap();
+        assert!(problems.is_empty());
+        assert_eq!(reloaded.tasks.len(),3);
+        for (before,after) in task_list.tasks.iter().zip(&reloaded.tasks){
+            assert_eq!((&before.data,&before.notes,before.priority,&before.recurrence),(&after.data,&after.notes,after.priority,&after.recurrence));
+            assert_eq!((before.id,&before.depends_on,before.due_date,&before.uid),(after.id,&after.depends_on,after.due_date,&after.uid));
+        }
+        assert_eq!(convert_csvtotl("text,uid\nOdd,a]b\n").unwrap().0.tasks[0].uid,None);
+
+        // columns by header in any order, bad rows are reported with their line
+        let sheet="Text,Due,Status\nPlan trip,2024-04-01,open\n\nBad date,someday,open\nDone thing,,done\n,2024-04-02,open\n";
+        let (imported,problems)=convert_csvtotl(sheet).unwrap();
+        assert_eq!(imported.tasks.len(),2);
+        assert!(imported.tasks[1].completed);
+        assert_eq!(problems.len(),2);
+        assert!(problems[0].starts_with("Line 4: due:"));
+        assert!(problems[1].starts_with("Line 6: text is empty"));
+        assert!(convert_csvtotl("title,due\nx,y\n").unwrap_err().contains("'title' is not a column"));
+
+        // append renumbers clashing ids and keeps dependencies, a file with bad rows is refused
+        let _ = std::fs::create_dir_all("testdata");
+        std::fs::write("testdata/import.csv",&data).unwrap();
+        std::fs::write("testdata/bad.csv",sheet).unwrap();
+        let mut store=MemoryStore::default();
+        let mut target=TaskList{ tasks:create_mocklist(2) };
+        target.ensure_ids();
+        let indexes=command_import(&mut target,Some("csv".to_string()),"testdata/import.csv".to_string(),false,&mut store).unwrap();
+        assert_eq!(indexes,vec![2,3,4]);
+        assert_eq!(target.tasks[4].depends_on,vec![target.tasks[2].id]);
+        assert!(target.validate().is_empty());
+        assert!(command_import(&mut target,None,"testdata/bad.csv".to_string(),true,&mut store).is_err());
+        assert_eq!(target.tasks.len(),5);
+        command_import(&mut target,None,"testdata/import.csv".to_string(),true,&mut store).unwrap();
+        assert_eq!(target.tasks.len(),3);
+        // a row with the uid of a task updates that task
+        std::fs::write("testdata/uid.csv","text,uid\nRenamed,cal-42@example.com\n").unwrap();
+        command_import(&mut target,None,"testdata/uid.csv".to_string(),false,&mut store).unwrap();
+        assert_eq!((target.tasks.len(),target.tasks[1].data.as_str()),(3,"Renamed"));
+
+        // text over several lines is joined, and a dependency cycle is not let in
+        let (joined,_)=convert_csvtotl("text,notes
+\"Pack\r\nthe car\",\"keys\rmap\"\n").unwrap();
+        assert_eq!(joined.tasks[0].data,"Pack the car");
+        assert_eq!(joined.tasks[0].notes,vec!["keys","map"]);
+        std::fs::write("testdata/cycle.csv","id,text,depends_on\n1,First,2\n2,Second,1\n").unwrap();
+        let error=command_import(&mut target,None,"testdata/cycle.csv".to_string(),false,&mut store).unwrap_err();
+        assert!(error.contains("dependency cycle"));
+        assert_eq!(target.tasks.len(),3);
+    }
+
+    #[test]
+    fn test_storage(){
+        use crate::rusty_serializer::*;
+
+        let mut task_list=TaskList{ tasks:create_mocklist(3) };
+        task_list.ensure_ids();
+        task_list.backfill_timestamps(Utc::now());
+        task_list.tasks[0].recurrence=Some("weekly mon,fri".parse().unwrap());
+        task_list.tasks[0].notes=vec!["a note".to_string()];
+        task_list.tasks[1].priority=Some('A');
+        task_list.tasks[1].due_date=Some(Utc::now());
+        task_list.tasks[2].depends_on=vec![task_list.tasks[0].id];
+        for name in ["markdown","json","yaml"]{
+            let serializer=serializer_named(name).unwrap();
+            let data=serializer.serialize(&task_list).unwrap();
+            let reloaded=serializer.deserialize(&data).unwrap();
+            assert_eq!(reloaded.tasks.len(),3,"{name}");
+            assert_eq!(reloaded.tasks[0].recurrence,task_list.tasks[0].recurrence,"{name}");
+            assert_eq!((reloaded.tasks[1].priority,&reloaded.tasks[2].depends_on),(Some('A'),&task_list.tasks[2].depends_on),"{name}");
+        }
+        // JSON and YAML keep every field exactly
+        let json=JsonSerializer.serialize(&task_list).unwrap();
+        assert_eq!(JsonSerializer.deserialize(&json).unwrap().tasks,task_list.tasks);
+        assert!(json.contains("\"recurrence\": \"weekly mon,fri\""));
+        assert!(!json.contains("completed_date"));
+        assert_eq!(YamlSerializer.deserialize(&YamlSerializer.serialize(&task_list).unwrap()).unwrap().tasks,task_list.tasks);
+        assert_eq!(serializer_for("data/list.YML").name(),"yaml");
+        assert_eq!(serializer_for("data/list.md").name(),"markdown");
+
+        // files pick their format by extension and convert checks what it wrote
+        let _ = std::fs::create_dir_all("testdata");
+        for file in ["testdata/storage.json","testdata/storage.yaml","testdata/storage.md"]{
+            let _ = std::fs::remove_file(file);
+        }
+        let _ = save_tltofile("testdata/storage.json".to_string(),task_list.clone());
+        assert!(std::fs::read_to_string("testdata/storage.json").unwrap().trim_start().starts_with('{'));
+        assert_eq!(load_tlfromfile("testdata/storage.json".to_string()).tasks,task_list.tasks);
+        let stored=load_tlfromfile("testdata/storage.json".to_string());
+        command_convert(&stored,"testdata/storage.json","testdata/storage.yaml".to_string()).unwrap();
+        assert_eq!(load_tlfromfile("testdata/storage.yaml".to_string()).tasks,task_list.tasks);
+        assert!(command_convert(&stored,"testdata/storage.json","testdata/storage.yaml".to_string()).is_err());
+        let (store,converted)=command_convert(&task_list,"testdata/storage.json","testdata/storage.md".to_string()).unwrap();
+        assert_eq!(store.location(),"testdata/storage.md");
+        assert_eq!(converted.tasks.len(),3);
+
+        std::fs::write("testdata/broken.json","{ \"tasks\": [ { \"data\": 5 } ] }").unwrap();
+        assert!(try_load_tlfromfile("testdata/broken.json").is_err());
+    }
+
+    #[test]
+    fn test_sqlite(){
+        use crate::rusty_store::*;
+
+        let _ = std::fs::create_dir_all("testdata");
+        for file in ["testdata/store.db","testdata/store-export.md"]{
+            let _ = std::fs::remove_file(file);
+        }
+        let mut task_list=TaskList{ tasks:create_mocklist(4) };
+        task_list.ensure_ids();
+        task_list.backfill_timestamps(Utc::now());
+        task_list.tasks[0].data="Call #work +phone".to_string();
+        task_list.tasks[0].notes=vec!["one".to_string(),"two".to_string()];
+        task_list.tasks[1].recurrence=Some("every 3d".parse().unwrap());
+        task_list.tasks[1].priority=Some('B');
+        task_list.tasks[2].depends_on=vec![task_list.tasks[0].id,task_list.tasks[1].id];
+        task_list.tasks[3].due_date=Some(Utc::now());
+        task_list.tasks[3].uid=Some("call@example.com".to_string());
+        assert_eq!(storage_format_for("data/tasks.sqlite3"),"sqlite");
+        // a new database may be written by the store that created it
+        let mut store=store_for("testdata/store.db").unwrap();
+        store.save(&task_list).unwrap();
+        assert_eq!(load_tlfromfile("testdata/store.db".to_string()).tasks,task_list.tasks);
+
+        // only the Tasks that changed are written again
+        let connection=rusqlite::Connection::open("testdata/store.db").unwrap();
+        connection.execute_batch("CREATE TABLE writes(id INTEGER); CREATE TRIGGER count_writes AFTER INSERT ON tasks BEGIN INSERT INTO writes VALUES(new.id); END;").unwrap();
+        let writes=|| connection.query_row("SELECT COUNT(*) FROM writes",[],|row| row.get::<_,i64>(0)).unwrap();
+        let _=command_complete(&mut task_list,4,store.as_mut());
+        assert_eq!(writes(),1);
+        // the Tasks below a removed one only move and get a new position, only the one that depended on it is written
+        let _=command_remove(&mut task_list,1,store.as_mut());
+        assert_eq!(writes(),2);
+        let tags:i64=connection.query_row("SELECT COUNT(*) FROM task_tags WHERE tag='#work'",[],|row| row.get(0)).unwrap();
+        assert_eq!(tags,0);
+        let reloaded=load_tlfromfile("testdata/store.db".to_string());
+        assert_eq!(reloaded.tasks,task_list.tasks);
+        assert_eq!(reloaded.tasks[1].depends_on,vec![task_list.tasks[0].id]);
+        // testdata is outside ./data, so opened again the database is only read, like a Markdown file there
+        let mut again=store_for("testdata/store.db").unwrap();
+        assert_eq!(again.load().unwrap().tasks,task_list.tasks);
+        assert!(again.save(&task_list).unwrap_err().to_string().contains("not overwritten"));
+        assert_eq!(writes(),2);
+
+        command_convert(&reloaded,"testdata/store.db","testdata/store-export.md".to_string()).unwrap();
+        assert_eq!(load_tlfromfile("testdata/store-export.md".to_string()).tasks.len(),3);
+    }
+
+    #[test]
+    fn test_taskstore(){
+        use crate::rusty_store::*;
+
+        // commands save through any store, a list in memory is enough to check them
+        let mut store=MemoryStore::default();
+        let mut task_list=TaskList{ tasks:Vec::new() };
+        command_add(&mut task_list,"Write report".to_string(),"".to_string(),&mut store).unwrap();
+        command_add(&mut task_list,"Send report".to_string(),"2024-03-30".to_string(),&mut store).unwrap();
+        command_depend(&mut task_list,2,1,&mut store).unwrap();
+        command_complete(&mut task_list,1,&mut store).unwrap();
+        assert_eq!(store.saves,4);
+        assert_eq!(store.load().unwrap().tasks,task_list.tasks);
+        assert!(command_remove(&mut task_list,5,&mut store).is_err());
+        assert_eq!(store.saves,4);
+        assert_eq!(store.location(),"memory");
+
+        // a save that fails goes back to the command instead of only being printed
+        let _ = std::fs::create_dir_all("testdata");
+        std::fs::write("testdata/outside.md","").unwrap();
+        assert!(store_for("testdata/outside.md").unwrap().save(&task_list).is_err());
+
+        // a batch keeps the saves and writes once at commit
+        let _ = std::fs::create_dir_all("testdata");
+        let _ = std::fs::remove_file("testdata/taskstore.md");
+        let mut file_store=store_for("testdata/taskstore.md").unwrap();
+        let mut batch=BatchStore::new(file_store.as_mut());
+        command_edit(&mut task_list,1,"Write the report".to_string(),&mut batch).unwrap();
+        command_edit(&mut task_list,2,"Send the report".to_string(),&mut batch).unwrap();
+        assert!(!std::path::Path::new("testdata/taskstore.md").exists());
+        assert_eq!(batch.load().unwrap().tasks[1].data,"Send the report");
+        batch.commit().unwrap();
+        assert_eq!(file_store.load().unwrap().tasks[0].data,"Write the report");
+
+        // a dropped batch writes nothing
+        let mut batch=BatchStore::new(file_store.as_mut());
+        command_remove(&mut task_list,1,&mut batch).unwrap();
+        drop(batch);
+        assert_eq!(file_store.load().unwrap().tasks.len(),2);
+
+        // commands that open another list need the prompt's store
+        let (command,args)=parse_input_commands("load testdata/taskstore.md");
+        assert!(handle_command(resolve_command(&command).unwrap(),args,&mut task_list,&mut store).is_err());
+    }
+
+    #[test]
+    fn test_migrate(){
+        use crate::rusty_migrate::*;
+
+        let current=convert_tltostring(TaskList{ tasks:create_mocklist(2) });
+        assert_eq!(markdown_version(&current),Ok(FORMAT_VERSION));
+        assert_eq!(migrate_markdown(&current).unwrap(),(current.clone(),FORMAT_VERSION));
+        assert!(markdown_version("# TaskList: [Version: two]\r\n").is_err());
+
+        // text that looks like fields stays text
+        let mut tricky=create_mocklist(4);
+        for (task,text) in tricky.iter_mut().zip(["[Priority: A] Call Bob","[Id: 7] [Uid: x] Seven","\\[Priority: B] odd","[draft] Plan"]){
+            task.data=text.to_string();
+        }
+        let reloaded=convert_stringtotl(convert_tltostring(TaskList{ tasks:tricky.clone() }));
+        assert_eq!(reloaded.tasks.iter().map(|task| task.data.as_str()).collect::<Vec<_>>(),tricky.iter().map(|task| task.data.as_str()).collect::<Vec<_>>());
+        assert!(reloaded.tasks.iter().all(|task| task.priority.is_none() && task.uid.is_none()));
+        let (upgraded,_)=migrate_markdown("# TaskList:\r\n - [ ] [Due: ] [Completed: ] [Priority: A] Pay\r\n - [ ] [Due: ] [Completed: ] [Id: 2] [Priority: B] [Id: 9] Nine\r\n").unwrap();
+        let upgraded=convert_stringtotl(upgraded);
+        assert_eq!((upgraded.tasks[0].data.as_str(),upgraded.tasks[0].priority),("[Priority: A] Pay",None));
+        assert_eq!((upgraded.tasks[1].data.as_str(),upgraded.tasks[1].priority),("Nine",Some('B')));
+
+        // files from before the version marker are read upgraded, and the original is kept when they are saved
+        let old="\r\n# TaskList:\r\n - [ ] [Due: ] [Completed: ] [Id: 1] Old task\r\n    a note\r\n";
+        assert_eq!(markdown_version(old),Ok(1));
+        let (upgraded,version)=migrate_markdown(old).unwrap();
+        assert_eq!(version,1);
+        assert!(upgraded.contains(&format!("# TaskList: [Version: {FORMAT_VERSION}]")));
+        let _ = std::fs::create_dir_all("testdata");
+        for file in ["testdata/migrate.md","testdata/migrate.md.v1.bak"]{
+            let _ = std::fs::remove_file(file);
+        }
+        std::fs::write("testdata/migrate.md",old).unwrap();
+        let mut store=crate::rusty_store::store_for("testdata/migrate.md").unwrap();
+        let loaded=store.load().unwrap();
+        assert_eq!((loaded.tasks[0].data.as_str(),&loaded.tasks[0].notes),("Old task",&vec!["a note".to_string()]));
+        assert_eq!(std::fs::read_to_string("testdata/migrate.md").unwrap(),old);
+        assert!(!std::path::Path::new("testdata/migrate.md.v1.bak").exists());
+        // testdata is outside ./data, so the file itself is not replaced here, only the backup before it is checked
+        let _ = store.save(&loaded);
+        assert_eq!(std::fs::read_to_string("testdata/migrate.md.v1.bak").unwrap(),old);
+
+        // a newer file is refused instead of being read without what this version does not know
+        std::fs::write("testdata/newer.md","# TaskList: [Version: 99]\r\n - [ ] [Due: ] [Completed: ] [Id: 1] [Colour: red] Paint\r\n").unwrap();
+        assert!(try_load_tlfromfile("testdata/newer.md").unwrap_err().to_string().contains("newer rusty-tasks"));
+    }
+
+    #[test]
+    fn test_check(){
+        use crate::rusty_store::*;
+
+        // everything the app writes reads back without problems
+        let mut task_list=TaskList{ tasks:create_mocklist(3) };
+        task_list.ensure_ids();
+        task_list.backfill_timestamps(Utc::now());
+        let _=command_complete(&mut task_list,1,&mut MemoryStore::default());
+        task_list.tasks[1].recurrence=Some("weekly mon".parse().unwrap());
+        task_list.tasks[1].priority=Some('C');
+        task_list.tasks[1].due_date=Some(Utc::now());
+        task_list.tasks[2].depends_on=vec![task_list.tasks[0].id,task_list.tasks[1].id];
+        task_list.tasks[2].notes=vec!["a note".to_string()];
+        assert!(find_parse_problems(&convert_tltostring(task_list.clone())).is_empty());
+
+        // each line that would be lost or read in part is reported with its number
+        let data="My tasks\r\n\r\n# TaskList: [Version: 2]\r\n    orphan note\r\n - [ ] [Due: someday] [Completed: ] [Id: 1] [Priority: 7] Pay rent\r\n    a note\r\n - [√] [Due: ] [Completed: ] [Id: x] [Deps: 1,a] Done\r\nrandom text\r\n";
+        let problems=find_parse_problems(data);
+        assert_eq!(problems.iter().map(|(n,_)| *n).collect::<Vec<_>>(),vec![1,4,5,5,7,7,8]);
+        assert!(problems[2].1.starts_with("due date 'someday'"));
+        assert!(problems[6].1.ends_with("is not a task line"));
+        assert!(format_parse_problems(&problems).starts_with("Line 1: 'My tasks' is before the '# TaskList:' header\r\nLine 4:"));
+        assert_eq!(find_parse_problems("just text").len(),1);
+
+        // strict loading refuses the file, otherwise it opens but is never overwritten
+        let _ = std::fs::create_dir_all("testdata");
+        for file in ["testdata/lossy.md","testdata/check.md"]{
+            let _ = std::fs::remove_file(file);
+        }
+        std::fs::write("testdata/lossy.md",data).unwrap();
+        let mut strict=FileStore{ path:"testdata/lossy.md".to_string(), strict:true };
+        assert!(strict.load().unwrap_err().to_string().contains("Line 8"));
+        let mut lenient=FileStore{ path:"testdata/lossy.md".to_string(), strict:false };
+        let loaded=lenient.load().unwrap();
+        assert_eq!(loaded.tasks.len(),2);
+        assert!(lenient.save(&loaded).unwrap_err().to_string().contains("would drop lines"));
+        assert_eq!(std::fs::read_to_string("testdata/lossy.md").unwrap(),data);
+        assert!(command_check("testdata/lossy.md".to_string()).unwrap_err().contains("7 problems"));
+        let _ = save_tltofile("testdata/check.md".to_string(),task_list);
+        assert!(command_check("testdata/check.md".to_string()).is_ok());
+        assert!(matches!(resolve_command(&expand_alias("lint",&Default::default())),Ok(TASKCOM::Check)));
+    }
+
+    #[test]
+    fn test_workspaces(){
+        use crate::rusty_workspace::*;
+        use crate::rusty_store::store_for;
+
+        // saves check against the config, whose lists are in ./data, so the lists of this test are there too
+        let _ = std::fs::remove_dir_all("data/test-workspaces");
+        let workspace=Workspace{
+            lists_dir:"data/test-workspaces/lists".to_string(),
+            data_file:"data/test-workspaces/default.db".to_string(),
+            storage_format:"sqlite".to_string()
+        };
+        assert_eq!(workspace.list_names(),vec!["default"]);
+        assert_eq!(workspace.active_list_path(),"data/test-workspaces/default.db");
+        let mut store=store_for(&workspace.data_file).unwrap();
+        let mut task_list=TaskList{ tasks:create_mocklist(3) };
+        task_list.ensure_ids();
+        task_list.tasks[2].depends_on=vec![task_list.tasks[0].id];
+        store.save(&task_list).unwrap();
+
+        // a new list becomes the active one, also for the next run
+        command_new_list(&mut task_list,&mut store,&workspace,"work").unwrap();
+        assert_eq!((store.location().as_str(),task_list.tasks.len()),("data/test-workspaces/lists/work.db",0));
+        assert_eq!(workspace.active_list_path(),"data/test-workspaces/lists/work.db");
+        assert_eq!(workspace.list_name_for(&store.location()),"work");
+        assert!(command_new_list(&mut task_list,&mut store,&workspace,"work").is_err());
+        assert!(command_new_list(&mut task_list,&mut store,&workspace,"../escape").is_err());
+        assert!(command_use(&mut task_list,&mut store,&workspace,"nowhere").is_err());
+
+        // moved tasks leave this list and keep their dependency, copies stay
+        command_use(&mut task_list,&mut store,&workspace,"default").unwrap();
+        assert_eq!(command_move_tasks(&mut task_list,store.as_mut(),&workspace,&[1,3],"work",false),Ok(2));
+        assert_eq!(task_list.tasks.len(),1);
+        assert_eq!(command_move_tasks(&mut task_list,store.as_mut(),&workspace,&[1],"work",true),Ok(1));
+        assert!(command_move_tasks(&mut task_list,store.as_mut(),&workspace,&[1],"default",false).is_err());
+        let work=try_load_tlfromfile("data/test-workspaces/lists/work.db").unwrap();
+        assert_eq!(work.tasks.iter().map(|t| t.data.as_str()).collect::<Vec<_>>(),vec!["Mock Task 1","Mock Task 3","Mock Task 2"]);
+        assert_eq!(work.tasks[1].depends_on,vec![work.tasks[0].id]);
+        assert_eq!(try_load_tlfromfile("data/test-workspaces/default.db").unwrap().tasks.len(),1);
+
+        // renaming keeps the file format, deleting needs --force while tasks are left
+        command_rename_list(&mut store,&workspace,"work","office").unwrap();
+        assert_eq!(workspace.list_names(),vec!["default","office"]);
+        assert!(command_rename_list(&mut store,&workspace,"default","home").is_err());
+        assert!(command_delete_list(store.as_ref(),&workspace,"office",false).unwrap_err().contains("--force"));
+        assert!(command_delete_list(store.as_ref(),&workspace,"default",true).is_err());
+        command_delete_list(store.as_ref(),&workspace,"office",true).unwrap();
+        assert_eq!(workspace.list_names(),vec!["default"]);
+        assert_eq!(workspace.active_list_path(),"data/test-workspaces/default.db");
+
+        let _ = std::fs::remove_dir_all("data/test-workspaces");
+        // only removed when nothing else is in it
+        let _ = std::fs::remove_dir("data");
+
+        // saves replace the configured lists wherever they are, and no other files outside ./data
+        let workspace=Workspace{
+            lists_dir:"testdata/lists".to_string(),
+            data_file:"testdata/lists-default.db".to_string(),
+            storage_format:"sqlite".to_string()
+        };
+        std::fs::create_dir_all("testdata/lists").unwrap();
+        std::fs::write("testdata/lists-default.db","").unwrap();
+        std::fs::write("testdata/lists/notes.md","").unwrap();
+        std::fs::write("testdata/stray.md","").unwrap();
+        assert!(workspace.may_overwrite("testdata/lists/notes.md"));
+        assert!(workspace.may_overwrite("testdata/lists-default.db"));
+        assert!(!workspace.may_overwrite("testdata/stray.md"));
+        assert!(!workspace.may_overwrite("testdata/lists/../stray.md"));
+    }
+
+    #[test]
+    fn test_filesaveload(){        
+        let global_datafilepath:String="testdata/tasklist.md".to_string();
+        let global_tasklist=&mut load_tlfromfile(global_datafilepath.clone());
+        // create mock tasks
+        let _=global_tasklist.add_task(Task::new(false, "test".to_string()));
+        let _=global_tasklist.add_task(Task::new(true, "test2".to_string()));
+        let _=global_tasklist.add_task(Task::new(true, "test3".to_string()));
+        // Testing conversions and file save/load
+        let string_tasklist=convert_tltostring(global_tasklist.clone());
+        let string_totasklist=convert_stringtotl(string_tasklist.clone());
+        println!("STRING: {}\r\n",string_tasklist);
+        println!("TASKLIST: {:?}\r\n",string_totasklist);
+        let _ = save_tltofile(global_datafilepath.clone(), global_tasklist.clone());
+        let new_tasklist=load_tlfromfile(global_datafilepath.clone());
+        println!("TASKLIST after SAVE/LOAD: {:?}",new_tasklist);
+    }
 }
\ No newline at end of file
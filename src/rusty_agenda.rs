@@ -0,0 +1,131 @@
+use std::fmt;
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc};
+use colored::Colorize;
+use itertools::Itertools;
+
+use crate::rusty_tasks::*;
+use crate::rusty_calendar::week_start;
+use crate::rusty_config::settings;
+use crate::rusty_theme::palette;
+use crate::rusty_table::{ListOptions, TaskTable};
+
+/// Represents the agenda groups a due date can fall into, in display order.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum AgendaBucket{
+    Overdue,
+    Today,
+    Tomorrow,
+    ThisWeek,
+    Later,
+    NoDate
+}
+
+impl fmt::Display for AgendaBucket{
+    fn fmt(&self, f: &mut fmt::Formatter)->fmt::Result{
+        let bucket_string=match &self{
+            AgendaBucket::Overdue=>"Overdue",
+            AgendaBucket::Today=>"Today",
+            AgendaBucket::Tomorrow=>"Tomorrow",
+            AgendaBucket::ThisWeek=>"This week",
+            AgendaBucket::Later=>"Later",
+            AgendaBucket::NoDate=>"No date"
+        };
+        write!(f,"{bucket_string}")
+    }
+}
+
+impl AgendaBucket{
+    /// All buckets in the order the agenda prints them
+    pub fn into_iter() -> core::array::IntoIter<AgendaBucket, 6> {
+        [
+            AgendaBucket::Overdue,
+            AgendaBucket::Today,
+            AgendaBucket::Tomorrow,
+            AgendaBucket::ThisWeek,
+            AgendaBucket::Later,
+            AgendaBucket::NoDate
+        ]
+        .into_iter()
+    }
+
+    /// Picks the bucket for a due date, comparing calendar days in local time.
+    /// The week ends the day before the configured week start, so late in the week "This week" can be empty.
+    pub fn for_due_date(due_date:Option<DateTime<Utc>>,today:NaiveDate)->AgendaBucket{
+        let Some(due)=due_date else { return AgendaBucket::NoDate };
+        let due_day=due.with_timezone(&Local).date_naive();
+        let days_into_week=(7+today.weekday().num_days_from_monday()-week_start().num_days_from_monday())%7;
+        let days_until_week_end=6-days_into_week as i64;
+        match (due_day-today).num_days() {
+            d if d < 0=>AgendaBucket::Overdue,
+            0=>AgendaBucket::Today,
+            1=>AgendaBucket::Tomorrow,
+            d if d <= days_until_week_end=>AgendaBucket::ThisWeek,
+            _=>AgendaBucket::Later
+        }
+    }
+}
+
+/// Today's date on the local calendar
+pub fn local_today()->NaiveDate{
+    Local::now().date_naive()
+}
+
+/// True when an uncompleted Task is due on a day before today
+pub fn is_overdue(task:&Task)->bool{
+    !task.completed && AgendaBucket::for_due_date(task.due_date,local_today()) == AgendaBucket::Overdue
+}
+
+/// Groups the indexes of uncompleted Tasks by bucket, sorted by due date inside each bucket
+pub fn group_tasks(tl:&TaskList,today:NaiveDate)->Vec<(AgendaBucket,Vec<usize>)>{
+    AgendaBucket::into_iter()
+        .map(|bucket|{
+            let indexes:Vec<usize>=(0..tl.tasks.len())
+                .filter(|i| !tl.tasks[*i].completed)
+                .filter(|i| AgendaBucket::for_due_date(tl.tasks[*i].due_date,today) == bucket)
+                .sorted_by_key(|i| tl.tasks[*i].due_date)
+                .collect();
+            (bucket,indexes)
+        })
+        .collect()
+}
+
+/// Indexes of uncompleted Tasks due from today up to `days` days ahead
+pub fn upcoming_tasks(tl:&TaskList,today:NaiveDate,days:i64)->Vec<usize>{
+    (0..tl.tasks.len())
+        .filter(|i| !tl.tasks[*i].completed)
+        .filter(|i| match tl.tasks[*i].due_date {
+            Some(due)=>{
+                let d=(due.with_timezone(&Local).date_naive()-today).num_days();
+                (0..=days).contains(&d)
+            },
+            None=>false
+        })
+        .sorted_by_key(|i| tl.tasks[*i].due_date)
+        .collect()
+}
+
+/// Prints the given buckets with a heading each, skipping the empty ones
+pub fn print_agenda(tl:&TaskList,groups:&[(AgendaBucket,Vec<usize>)]){
+    let eol="\r\n";
+    let indent=settings().indent;
+    let spacing = " ".repeat(indent);
+    let colors=palette();
+    // one table for every bucket keeps the columns lined up across headings
+    let all:Vec<usize>=groups.iter().flat_map(|(_,indexes)| indexes.clone()).collect();
+    let table=TaskTable::new(tl,&all,&ListOptions::from_settings());
+    let mut result=String::new();
+    for (bucket,indexes) in groups.iter().filter(|(_,indexes)| !indexes.is_empty()){
+        let heading=match bucket {
+            AgendaBucket::Overdue=>format!("{bucket}:").color(colors.overdue).bold(),
+            _=>format!("{bucket}:").color(colors.label)
+        };
+        result+=&format!("{spacing}{heading}{eol}");
+        for line in indexes.iter().flat_map(|i| table.rows(tl,*i)){
+            result+=&format!("{spacing}{line}{eol}");
+        }
+    }
+    if result.is_empty() {
+        result=format!("{spacing}{}{eol}","Nothing due.".color(colors.muted));
+    }
+    println!("{eol}{result}");
+}
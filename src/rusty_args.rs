@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+/// Keys that are read as `key:value` arguments, any other word with a colon stays plain text
+pub const NAMED_KEYS:[&str;2]=["due","recur"];
+
+/// Represents the arguments of one command line after tokenizing.
+/// `positional` holds the comma separated arguments (always at least one, possibly empty),
+/// `named` holds `key:value` and `--key=value` pairs and `flags` holds bare `--flag`s.
+#[derive(Default,Debug,Clone,PartialEq)]
+pub struct CommandArgs{
+    pub positional: Vec<String>,
+    pub named: HashMap<String,String>,
+    pub flags: Vec<String>,
+    // the positional text with the commas and spacing it was typed with
+    typed: String,
+    // where the first comma is in `typed`
+    first_comma: Option<usize>,
+    // the argument text before tokenizing
+    raw: String
+}
+
+impl CommandArgs{
+    /// The positional arguments as the user typed them, without named arguments and flags
+    pub fn text(&self)->String{
+        self.typed.clone()
+    }
+
+    /// Everything after the first comma, for commands like `note 3,text` whose text may hold commas itself
+    pub fn text_after_first(&self)->String{
+        match self.first_comma {
+            Some(comma)=>self.typed[comma+1..].trim_start().to_string(),
+            None=>String::new()
+        }
+    }
+
+    /// The arguments exactly as typed, for file paths whose backslashes and quotes are not escapes
+    pub fn raw(&self)->&str{
+        &self.raw
+    }
+
+    /// Looks up a named argument by key
+    pub fn get(&self,key:&str)->Option<&String>{
+        self.named.get(key)
+    }
+
+    /// True when `--flag` was given
+    pub fn has_flag(&self,flag:&str)->bool{
+        self.flags.iter().any(|f| f == flag)
+    }
+}
+
+/// One word while it is being read, remembering where quoting started
+#[derive(Default)]
+struct WordBuilder{
+    text: String,
+    // byte offset of the first colon that was neither quoted nor escaped
+    colon: Option<usize>,
+    // how many leading bytes were typed without quotes or escapes
+    plain_prefix: usize,
+    plain: bool,
+    started: bool
+}
+
+impl WordBuilder{
+    fn push(&mut self,c:char,literal:bool){
+        if !self.started {
+            self.started=true;
+            self.plain=true;
+        }
+        if literal {
+            self.plain=false;
+        }else if c == ':' && self.colon.is_none() {
+            self.colon=Some(self.text.len());
+        }
+        self.text.push(c);
+        if self.plain {
+            self.plain_prefix=self.text.len();
+        }
+    }
+}
+
+/// Characters a backslash keeps literal, before anything else it is just a backslash
+const ESCAPABLE:[char;5]=['\\','"','\'',',',':'];
+
+/// Splits the text after a command into arguments.
+/// Commas separate arguments, whitespace separates words, quotes and backslashes keep text literal.
+/// A quote only opens at the start of a word or value, and one that is never closed stays as typed.
+pub fn tokenize_arguments(input:&str)->CommandArgs{
+    let mut literal_quotes=Vec::new();
+    loop {
+        match tokenize_with(input,&literal_quotes) {
+            Ok(args)=>return args,
+            Err(unclosed)=>literal_quotes.push(unclosed)
+        }
+    }
+}
+
+/// Tokenizes with the quotes at the given byte offsets read as plain characters,
+/// failing with the offset of a quote that is opened but never closed
+fn tokenize_with(input:&str,literal_quotes:&[usize])->Result<CommandArgs,usize>{
+    let mut args=CommandArgs{ raw:input.trim().to_string(), ..CommandArgs::default() };
+    let mut group:Vec<String>=Vec::new();
+    let mut word=WordBuilder::default();
+    let mut quote:Option<(char,usize)>=None;
+    // separators seen since the last positional word, added to the typed text with the next one
+    let mut separator=String::new();
+    let mut chars=input.char_indices().peekable();
+
+    while let Some((position,c))=chars.next(){
+        match (quote,c) {
+            (_,'\\')=>match chars.peek() {
+                Some((_,escaped)) if ESCAPABLE.contains(escaped) || escaped.is_whitespace()=>{
+                    word.push(*escaped,true);
+                    chars.next();
+                },
+                _=>word.push(c,quote.is_some())
+            },
+            (Some((q,_)),c) if c == q=>quote=None,
+            (Some(_),c)=>word.push(c,true),
+            (None,'"' | '\'') if !literal_quotes.contains(&position) && (word.text.is_empty() || word.text.ends_with([':','=']))=>{
+                quote=Some((c,position));
+                // "" is still a word, even if nothing else is typed
+                word.started=true;
+            },
+            (None,',')=>{
+                finish_word(&mut word,&mut group,&mut args,&mut separator);
+                args.positional.push(group.join(" "));
+                group.clear();
+                args.typed.push_str(&separator);
+                args.first_comma.get_or_insert(args.typed.len());
+                args.typed.push(',');
+                separator.clear();
+            },
+            (None,c) if c.is_whitespace()=>{
+                finish_word(&mut word,&mut group,&mut args,&mut separator);
+                separator.push(c);
+            },
+            (None,c)=>word.push(c,false)
+        }
+    }
+    if let Some((_,unclosed))=quote {
+        return Err(unclosed)
+    }
+    finish_word(&mut word,&mut group,&mut args,&mut separator);
+    args.positional.push(group.join(" "));
+    // commands without arguments still get a single empty one, like before
+    if args.positional.len() > 1 && args.positional.last().is_some_and(|p| p.is_empty()) && !input.trim_end().ends_with(',') {
+        args.positional.pop();
+    }
+    Ok(args)
+}
+
+/// Sorts a finished word into a flag, a named argument or plain text.
+/// Flags and named arguments take the spacing in front of them along when they leave the text.
+fn finish_word(word:&mut WordBuilder,group:&mut Vec<String>,args:&mut CommandArgs,separator:&mut String){
+    let finished=std::mem::take(word);
+    if !finished.started {
+        return
+    }
+    let text=finished.text;
+    let separator=std::mem::take(separator);
+
+    if finished.plain_prefix > 2 && text.starts_with("--") {
+        let flag=&text[2..];
+        match flag.split_once('=') {
+            Some((key,value))=>{ args.named.insert(key.to_lowercase(),value.to_string()); },
+            None=>args.flags.push(flag.to_lowercase())
+        }
+        return
+    }
+    if let Some(colon)=finished.colon.filter(|colon| *colon < finished.plain_prefix) {
+        let key=text[..colon].to_lowercase();
+        let value=&text[colon+1..];
+        if NAMED_KEYS.contains(&key.as_str()) && !value.is_empty() {
+            args.named.insert(key,value.to_string());
+            return
+        }
+    }
+    if !args.typed.is_empty() {
+        args.typed.push_str(&separator);
+    }
+    args.typed.push_str(&text);
+    group.push(text);
+}
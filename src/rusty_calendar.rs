@@ -0,0 +1,75 @@
+use chrono::{Datelike, Days, Local, Months, NaiveDate, Weekday};
+use colored::Colorize;
+
+use crate::rusty_tasks::*;
+use crate::rusty_config::settings;
+use crate::rusty_theme::palette;
+
+/// The first day of the week from the week_start setting, Monday by default
+pub fn week_start()->Weekday{
+    match settings().week_start.as_str() {
+        "sunday"=>Weekday::Sun,
+        _=>Weekday::Mon
+    }
+}
+
+/// Indexes of Tasks due on the given local calendar day
+pub fn tasks_on_day(tl:&TaskList,day:NaiveDate)->Vec<usize>{
+    (0..tl.tasks.len())
+        .filter(|i| tl.tasks[*i].due_date.is_some_and(|due| due.with_timezone(&Local).date_naive() == day))
+        .collect()
+}
+
+/// Renders a month grid, marking each day with the number of open Tasks due on it (*2, or *+ above nine)
+pub fn render_month(tl:&TaskList,month:NaiveDate,today:NaiveDate,first_weekday:Weekday)->String{
+    let eol="\r\n";
+    let indent=settings().indent;
+    let spacing = " ".repeat(indent);
+    let colors=palette();
+    let first=month.with_day(1).unwrap_or(month);
+    let next_month=first+Months::new(1);
+
+    let mut result=format!("{spacing}{}{eol}",first.format("%B %Y").to_string().color(colors.label));
+
+    // weekday names, starting from the configured first day
+    let mut header=String::new();
+    let mut weekday=first_weekday;
+    for _ in 0..7{
+        header+=&format!("{:>3}   ",&weekday.to_string()[..2]);
+        weekday=weekday.succ();
+    }
+    result+=&format!("{spacing}{}{eol}",header.trim_end().color(colors.muted));
+
+    // blank cells before the 1st
+    let offset=(7+first.weekday().num_days_from_monday()-first_weekday.num_days_from_monday())%7;
+    let mut row=" ".repeat(6*offset as usize);
+    let mut day=first;
+    while day < next_month{
+        let due:Vec<&Task>=tasks_on_day(tl,day).iter().map(|i| &tl.tasks[*i]).collect();
+        let open=due.iter().filter(|t| !t.completed).count();
+        let marker=match open {
+            0=>"   ".to_string(),
+            1..=9=>format!("*{open} "),
+            _=>"*+ ".to_string()
+        };
+        let number=format!("{:>3}",day.day());
+        let number=match (open,due.is_empty()) {
+            (0,true)=>number.normal(),
+            (0,false)=>number.color(colors.done),
+            _ if day < today=>number.color(colors.overdue).bold(),
+            _=>number.color(colors.due).bold()
+        };
+        let number=if day == today { number.reversed() } else { number };
+        row+=&format!("{number}{}",marker.color(colors.due));
+
+        day=day+Days::new(1);
+        if day.weekday() == first_weekday {
+            result+=&format!("{spacing}{}{eol}",row.trim_end());
+            row=String::new();
+        }
+    }
+    if !row.trim().is_empty() {
+        result+=&format!("{spacing}{}{eol}",row.trim_end());
+    }
+    result
+}
@@ -1,243 +1,1629 @@
-use std::collections::HashMap;
-use std::io::Error;
-use std::io::ErrorKind;
-use std::path::Path;
-use chrono::{NaiveDate, NaiveTime, Utc};
-
-use crate::rusty_tasks::*;
-use crate::rusty_files::*;
-
-/// Returns help information for commands
-pub fn command_help(command:Option<String>)->Result<String,String>{
-    let debug = false;
-    if debug{
-        if let Some(com) = command.clone() {
-            println!("HELP command was:{}",com.len());
-        } 
-    }
-    let eol="\r\n";
-    let indent=4;
-    let spacing = " ".repeat(indent);
-    let commandlist=list_task_commands();
-    if debug {println!("{commandlist}");}
-
-    let noneempty_case = &format!(
-r#"
-{spacing}Please use these commands to interact:{eol}
-{spacing}{commandlist}{eol}    
-{spacing}For further help type 'help command' like 'help add' no quotes.
-"#);
-
-    let response_hash = HashMap::from([
-        ("list", r#"
-        The LIST command will LIST out your current tasks.
-        "#),
-        ("add", r#"
-        The ADD command will ADD a task when used like so:
-    
-        add This is a test!
-    
-        or to add with a due date:
-    
-        add Testing,2024-03-30 12:00:00 -05:00
-        "#),
-        ("remove", r#"
-        The REMOVE command will REMOVE a task when used like so:
-    
-        remove 1
-    
-        This removes task 1 from your tasklist.
-        "#),
-        ("complete", r#"
-        The COMPLETE command will COMPLETE a task when used like so:
-    
-        complete 1
-    
-        This completes task 1 from your tasklist.
-        "#),
-        ("exit", r#"
-        The EXIT command EXITS the CLI Rusty Tasks process.
-        "#),
-        ("empty_string", noneempty_case.as_str()),
-    ]);
-    
-    let help_info = match command {
-        Some(value) if value == "list" =>{response_hash["list"]}
-        Some(value) if value == "add"=>{response_hash["add"]}
-        Some(value) if value == "remove"=>{response_hash["remove"]}
-        Some(value) if value == "complete"=>{response_hash["complete"]},
-        Some(value) if value =="exit"=>{response_hash["exit"]},
-        Some(value) if value == "" =>{response_hash["empty_string"]}
-        None=>{response_hash["empty_string"]}
-        _=>"-1"
-    };
-    if help_info == "-1" {
-        return Err("Invalid HELP command please try again.".to_string());
-    }
-    Ok(help_info.to_string())
-}
-
-/// Prints all Tasks in TaskList
-pub fn command_list(global_tasks:&mut TaskList){
-    global_tasks.print_pretty();
-}
-
-/// Adds new Task to TaskList
-pub fn command_add(global_tasks:&mut TaskList,data:String,date:String,global_datafilepath:String)->Result<(),String>{
-    let default_date_format="%Y-%m-%d %H:%M:%S %z";
-    let mut temp_task = Task::new(false, data);
-    let default_time = NaiveTime::default(); // equivelant to NaiveTime::from_hms_opt(0, 0, 0).unwrap()
-
-    if date != "" {
-        let parsed_date=NaiveDate::parse_from_str(date.as_str(), default_date_format)
-                                        .ok()
-                                        .unwrap_or_default()
-                                        .and_time(default_time);
-        let dt_utc=parsed_date.and_utc();
-        //let dt_localtimezone : DateTime<Local> = dt_utc.with_timezone(&Local);
-        temp_task.due_date=Some(dt_utc);
-    }else{
-        temp_task.due_date=None;
-    }
-    
-    match global_tasks.add_task(temp_task){
-        Ok(_)=>{
-            let _ = save_tltofile(global_datafilepath, global_tasks.clone());
-            return Ok(())
-        },
-        Err(_)=>{return Err("Invalid ADD command please try again.".to_string())}
-    };
-}
-
-/// Removes a Task in TaskList by Index
-pub fn command_remove(global_tasks:&mut TaskList,mut index:usize,global_datafilepath:String)->Result<(),String>{
-    index=index.overflowing_sub(1).0;//prevent panic, handle elegantly later
-    if let Ok(_) = global_tasks.delete_task(index) {
-        let _ = save_tltofile(global_datafilepath, global_tasks.clone());
-        return Ok(())
-    }else{
-        return Err("Invalid REMOVE command please try again.".to_string())
-    }
-}
-
-/// Completes a Task in TaskList by Index
-pub fn command_complete(global_tasks:&mut TaskList,mut index:usize,global_datafilepath:String)->Result<(),String>{
-    index=index.overflowing_sub(1).0;//prevent panic, handle elegantly later
-    if let Ok(_) = global_tasks.toggle_completed_task(index){
-        // this must run before the save_tltofile
-        match global_tasks.tasks[index].completed{
-            true=>global_tasks.tasks[index].completed_date=Some(Utc::now()),
-            false=>{}
-        }
-        match save_tltofile(global_datafilepath, global_tasks.clone()) {
-            Ok(value)=>Some(value),
-            Err(_)=>{
-                eprintln!("Complete Command was unable to save to file.");
-                None
-            }
-        };
-        return Ok(())
-    }else{
-        return Err("Invalid COMPLETE command please try again.".to_string())
-    }  
-}
-
-/// Ends the process and exits to terminal
-pub fn command_exit(){
-    std::process::exit(0);
-}
-
-/// Parses user input into command and arguments
-pub fn parse_input_commands(input: &str) -> (String, Vec<String>){
-    let mut parts = input.splitn(2, char::is_whitespace);
-
-    // Parse command
-    let command = parts.next().unwrap_or("").to_lowercase();
-
-    // Parse arguments
-    let arguments:Vec<String> = match parts.next() {
-        Some(args)=>args.split(",").map(|arg| arg.trim().to_string()).collect(),
-        None=>vec![String::from("")]// requires string inside or else there will be no "arguments[0]"
-    };    
-
-    (command, arguments)
-}
-
-/// Converts command struct into function calls to run command
-pub fn handle_command(command:TASKCOM,arguments:Vec<String>,global_tasks:&mut TaskList,global_datafilepath:String)->Result<(),String>{
-    match command{
-        TASKCOM::Help=>{
-            let help = match command_help(arguments.get(0).map(|s| s.trim().to_string())){
-                Ok(text)=>{text},
-                Err(error)=>{error}
-            };
-            println!("{}",help);
-            Ok(())
-        },
-        TASKCOM::List=>Ok(command_list(global_tasks)),
-        TASKCOM::Add=>{
-            if arguments.len()>1{
-                command_add(global_tasks,arguments[0].to_string(), arguments[1].to_string(),global_datafilepath)?;
-            }else{
-                command_add(global_tasks,arguments[0].to_string(), "".to_string(),global_datafilepath)?;
-            }
-            command_list(global_tasks);
-            Ok(())
-        },
-        TASKCOM::Remove=>{
-            let index=match arguments[0].parse::<usize>() {
-                Ok(index)=>{index},
-                Err(_e)=>{return Err("Invalid REMOVE command please try again.".to_string())}
-            };
-            command_remove(global_tasks,index,global_datafilepath)?;
-            command_list(global_tasks);
-            Ok(())
-        },
-        TASKCOM::Complete=>{
-            let index=match arguments[0].parse::<usize>() {
-                Ok(index)=>{index},
-                Err(_e)=>{return Err("Invalid COMPLETE command please try again.".to_string())}
-            };
-            command_complete(global_tasks,index,global_datafilepath)?;
-            command_list(global_tasks);
-            Ok(())
-        },
-        TASKCOM::Exit=>Ok(command_exit()),
-        TASKCOM::Unknown=> Err("Invalid command. Try 'help' for a list of commands.".to_string())
-    }
-}
-
-/// allows user to load a tasklist file
-pub fn command_loadfile(global_datafilepath:&mut String,filepath:String)->Result<(),Error>{
-    // validate shape of filepath is a filepath
-    let validate_filepath = Path::new(&filepath).parent();
-    // validate the filepath exists
-    match validate_filepath {
-        Some(_)=>{},
-        None=>{return Err(Error::new(ErrorKind::Other, "Invalid filepath provided for command_saveas."))}
-    }
-    set_defaultfilepath(global_datafilepath, filepath.clone())?;
-    load_tlfromfile(global_datafilepath.clone());
-    Ok(())
-}
-
-/// allows user to save a tasklist file
-pub fn command_savefile_as(global_tasks:&mut TaskList,global_datafilepath:&mut String,filepath:String)->Result<(),Error>{
-    // validate shape of filepath is a filepath
-    let validate_filepath = Path::new(&filepath).parent();
-    // validate the filepath exists
-    match validate_filepath {
-        Some(_)=>{},
-        None=>{return Err(Error::new(ErrorKind::Other, "Invalid filepath provided for command_saveas."))}
-    }
-    // save to filepath
-    let _ = save_tltofile(filepath.clone(), global_tasks.clone())?;
-
-    // re-load from the filepath we saved to
-    load_tlfromfile(filepath.clone());
-
-    // set default filepath just like command_load
-    set_defaultfilepath(global_datafilepath, filepath.clone())?;
-
-    Ok(())
+use std::collections::HashMap;
+use std::io::Error;
+use std::path::Path;
+use std::str::FromStr;
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+
+use crate::rusty_tasks::*;
+use crate::rusty_files::*;
+use crate::rusty_recurrence::Recurrence;
+use crate::rusty_editor::{edit_text, prompt, summarize_changes};
+use crate::rusty_agenda::*;
+use crate::rusty_calendar::*;
+use crate::rusty_tui::run_tui;
+use crate::rusty_args::*;
+use crate::rusty_config::{settings, install_config, load_config, Config};
+use crate::rusty_theme::apply_color_mode;
+use crate::rusty_table::ListOptions;
+use crate::rusty_theme::palette;
+use crate::rusty_todotxt::{convert_todotxttotl, convert_tltotodotxt};
+use crate::rusty_ical::{convert_icaltotl, convert_tltoical, task_uid};
+use crate::rusty_csv::{convert_csvtotl, convert_tltocsv};
+use crate::rusty_store::{storage_format_for, store_for, TaskStore};
+use crate::rusty_serializer::serializer_for;
+use crate::rusty_workspace::{validate_list_name, Workspace, DEFAULT_LIST};
+use colored::Colorize;
+
+/// Returns help information for commands
+pub fn command_help(command:Option<String>)->Result<String,String>{
+    let debug = false;
+    if debug{
+        if let Some(com) = command.clone() {
+            println!("HELP command was:{}",com.len());
+        } 
+    }
+    let eol="\r\n";
+    let indent=settings().indent;
+    let spacing = " ".repeat(indent);
+    let commandlist=list_task_commands();
+    if debug {println!("{commandlist}");}
+
+    let noneempty_case = &format!(
+r#"
+{spacing}Please use these commands to interact:{eol}
+{spacing}{commandlist}{eol}    
+{spacing}For further help type 'help command' like 'help add' no quotes.
+{spacing}Commands can be shortened while they stay unambiguous, like 'comp 1', and ls, rm, q work too.
+"#);
+
+    let response_hash = HashMap::from([
+        ("list", r#"
+        The LIST command will LIST out your current tasks.
+    
+        The columns, dates and long text follow your config, or choose them for one list:
+    
+        list --columns="due,text"
+        list --relative
+        list --wrap
+        list --limit=20 --offset=40
+    
+        Lists taller than the screen are shown through $PAGER or a built-in pager.
+    
+        Columns are number, status, priority, due, completed, created, updated, recur and text.
+        --absolute and --truncate undo --relative and --wrap.
+        "#),
+        ("add", r#"
+        The ADD command will ADD a task when used like so:
+    
+        add This is a test!
+    
+        or to add with a due date, and optionally a recurrence:
+    
+        add Testing due:2024-03-30
+        add Stand-up prep due:2024-03-04 recur:daily
+    
+        Commas are part of the text. Use quotes or \ to keep a colon or quote as text:
+    
+        add Buy eggs, milk, bread
+        add "Read re:invoice thread" due:"2024-03-30 12:00:00 -05:00"
+    
+        The old 'add Testing,2024-03-30' form still works but is deprecated.
+        "#),
+        ("remove", r#"
+        The REMOVE command will REMOVE a task when used like so:
+    
+        remove 1
+    
+        This removes task 1 from your tasklist.
+        "#),
+        ("complete", r#"
+        The COMPLETE command will COMPLETE a task when used like so:
+    
+        complete 1
+    
+        This completes task 1 from your tasklist.
+        "#),
+        ("depend", r#"
+        The DEPEND command makes one task wait on another when used like so:
+    
+        depend 3,1
+    
+        Task 3 is now blocked until task 1 is completed.
+        "#),
+        ("undepend", r#"
+        The UNDEPEND command removes a dependency when used like so:
+    
+        undepend 3,1
+    
+        Task 3 no longer waits on task 1.
+        "#),
+        ("recur", r#"
+        The RECUR command makes a task repeat when it is completed, used like so:
+    
+        recur 1,daily
+        recur 1,weekly mon,fri
+        recur 1,monthly 15
+        recur 1,every 3d
+        recur 1,none
+    
+        Completing a repeating task keeps it as done and adds the next one with its due date moved on.
+        "#),
+        ("note", r#"
+        The NOTE command adds notes to a task when used like so:
+    
+        note 1,Steps to reproduce are in the ticket
+    
+        or to edit all notes of task 1 in $EDITOR:
+    
+        note 1
+        "#),
+        ("show", r#"
+        The SHOW command shows all details and notes of a task when used like so:
+    
+        show 1
+        "#),
+        ("edit-in-editor", r#"
+        The EDIT-IN-EDITOR command opens the whole list in $VISUAL or $EDITOR:
+    
+        edit-in-editor
+    
+        or just one task:
+    
+        edit-in-editor 1
+    
+        Your changes are checked and summarised before you confirm them.
+        "#),
+        ("sort", r#"
+        The SORT command reorders your tasks by created, updated, due, completed or text:
+    
+        sort due
+    
+        or newest first:
+    
+        sort updated --desc
+        "#),
+        ("filter", r#"
+        The FILTER command lists tasks whose date is before or after a date or an age in days:
+    
+        filter updated,before,30d
+        filter created,after,2024-03-01
+    
+        Fields are created, updated, due and completed.
+        "#),
+        ("agenda", r#"
+        The AGENDA command groups open tasks by due date:
+    
+        Overdue, Today, Tomorrow, This week, Later and No date.
+        "#),
+        ("today", r#"
+        The TODAY command lists open tasks that are overdue or due today.
+        "#),
+        ("overdue", r#"
+        The OVERDUE command lists open tasks whose due date has passed.
+        "#),
+        ("upcoming", r#"
+        The UPCOMING command lists open tasks due in the next days when used like so:
+    
+        upcoming 7d
+    
+        Without a number of days it looks 7 days ahead.
+        "#),
+        ("calendar", r#"
+        The CALENDAR command shows a month with the number of open tasks due each day:
+    
+        calendar
+        calendar 2024-03
+    
+        or to list the tasks due on one day:
+    
+        calendar 2024-03-15
+    
+        Weeks start on Monday, use 'config set week_start sunday' to change that.
+        "#),
+        ("edit", r#"
+        The EDIT command replaces the text of a task when used like so:
+    
+        edit 1,New text for task 1
+        "#),
+        ("tui", r#"
+        The TUI command opens the full-screen view. Keys:
+    
+        up/down or j/k to move, space to toggle complete, a to add, e to edit,
+        d to remove, / to filter, enter for details and q to go back.
+    
+        Start in it directly with: cargo run -- --tui
+        "#),
+        ("load", r#"
+        The LOAD command opens another task list file and makes it the default:
+    
+        load data/work.md
+        "#),
+        ("saveas", r#"
+        The SAVEAS command saves your tasks under a new file and makes it the default:
+    
+        saveas data/backup.md
+        "#),
+        ("config", r#"
+        The CONFIG command shows or changes your settings:
+    
+        config
+        config get date_format
+        config set indent 2
+        config set theme light
+        config set colors.label bright blue
+        config set aliases.t today
+    
+        Changes are saved to the config file right away, 'config path' shows where it is.
+        "#),
+        ("import", r#"
+        The IMPORT command adds the tasks from a todo.txt, iCalendar (.ics) or CSV file to your list:
+    
+        import todo.txt
+        import calendar.ics
+        import review.csv --dry-run
+        import review.csv --replace
+        import --format=todotxt data/phone-tasks
+    
+        Priorities, due, creation and completion dates are kept, +project and @context words
+        stay in the task text. From .ics files only the VTODOs are read, not events.
+        CSV columns are found by their header, text is the only one needed.
+        --dry-run shows what would be imported and every row that could not be read,
+        a file with such rows is not imported at all. --replace swaps out the whole list,
+        --append adds to it; without either the import_mode setting decides.
+        "#),
+        ("export", r#"
+        The EXPORT command writes your tasks as todo.txt, iCalendar or CSV, to a file or to the screen:
+    
+        export --format todotxt
+        export --format=todotxt data/todo.txt
+        export data/tasks.ics
+        export --format csv data/review.csv
+    
+        Each task in an .ics file is a VTODO that keeps its UID between exports, so calendar
+        clients update it instead of adding a copy. CSV keeps every field, with dates in ISO 8601.
+        todo.txt and .ics leave out dependencies and repeats, todo.txt also leaves out notes.
+        "#),
+        ("convert", r#"
+        The CONVERT command stores a task list in another format, picked by the file extension:
+    
+        convert data/tasklist.json
+        convert data/tasklist.md data/archive.yaml
+    
+        convert data/tasklist.db
+        convert data/tasklist.db data/export.md
+    
+        With one file the open list is converted and the new file becomes the default.
+        The formats are Markdown (.md), JSON (.json), YAML (.yaml or .yml) and SQLite
+        (.db, .sqlite or .sqlite3), files with another extension use the storage_format
+        setting. The new file must not exist yet.
+        "#),
+        ("check", r#"
+        The CHECK command, or LINT, lists every line of a task list file that can not be read
+        and why, without changing anything:
+    
+        check
+        check data/archive.md
+    
+        Without a file the open list is checked. A list with such lines opens, but is not saved
+        until they are fixed, so nothing is dropped. Set strict_load = true in the config, or
+        start with --strict, to refuse to open it at all.
+        "#),
+        ("lists", r#"
+        The LISTS command shows every named list and how many of its tasks are open.
+        The active list is marked with *, its name is also shown in the prompt.
+    
+        The default list is the data_file setting, the others are files in the lists_dir
+        setting (data/lists). The active list is remembered for the next run.
+        "#),
+        ("use", r#"
+        The USE command switches to another list:
+    
+        use work
+        use default
+        "#),
+        ("new-list", r#"
+        The NEW-LIST command creates an empty list and switches to it:
+    
+        new-list home
+    
+        Names may have letters, digits, - and _. New lists use the storage_format setting.
+        "#),
+        ("rename-list", r#"
+        The RENAME-LIST command renames a list, the default list keeps its name:
+    
+        rename-list home house
+        "#),
+        ("delete-list", r#"
+        The DELETE-LIST command deletes a list that is not the active or the default one:
+    
+        delete-list house
+        delete-list house --force
+    
+        A list that still has tasks is only deleted with --force.
+        "#),
+        ("move", r#"
+        The MOVE command moves tasks to another list, the last word is the list:
+    
+        move 3 work
+        move 2,5 home
+    
+        Moved tasks get new numbers there and keep the dependencies they have on each other.
+        "#),
+        ("copy", r#"
+        The COPY command copies tasks to another list and keeps them in this one:
+    
+        copy 3 work
+        copy 2,5 home
+        "#),
+        ("next", r#"
+        The NEXT command lists only the tasks that are not completed and not blocked.
+        "#),
+        ("exit", r#"
+        The EXIT command EXITS the CLI Rusty Tasks process.
+        "#),
+        ("empty_string", noneempty_case.as_str()),
+    ]);
+    
+    let help_info = match command {
+        Some(value) if value == "list" =>{response_hash["list"]}
+        Some(value) if value == "add"=>{response_hash["add"]}
+        Some(value) if value == "remove"=>{response_hash["remove"]}
+        Some(value) if value == "complete"=>{response_hash["complete"]},
+        Some(value) if value == "depend"=>{response_hash["depend"]},
+        Some(value) if value == "undepend"=>{response_hash["undepend"]},
+        Some(value) if value == "next"=>{response_hash["next"]},
+        Some(value) if value == "recur"=>{response_hash["recur"]},
+        Some(value) if value == "note"=>{response_hash["note"]},
+        Some(value) if value == "show"=>{response_hash["show"]},
+        Some(value) if value == "edit-in-editor"=>{response_hash["edit-in-editor"]},
+        Some(value) if value == "sort"=>{response_hash["sort"]},
+        Some(value) if value == "filter"=>{response_hash["filter"]},
+        Some(value) if value == "agenda"=>{response_hash["agenda"]},
+        Some(value) if value == "today"=>{response_hash["today"]},
+        Some(value) if value == "overdue"=>{response_hash["overdue"]},
+        Some(value) if value == "upcoming"=>{response_hash["upcoming"]},
+        Some(value) if value == "calendar"=>{response_hash["calendar"]},
+        Some(value) if value == "edit"=>{response_hash["edit"]},
+        Some(value) if value == "tui"=>{response_hash["tui"]},
+        Some(value) if value == "load"=>{response_hash["load"]},
+        Some(value) if value == "saveas"=>{response_hash["saveas"]},
+        Some(value) if value == "config"=>{response_hash["config"]},
+        Some(value) if value == "import"=>{response_hash["import"]},
+        Some(value) if value == "export"=>{response_hash["export"]},
+        Some(value) if value == "convert"=>{response_hash["convert"]},
+        Some(value) if value == "check"=>{response_hash["check"]},
+        Some(value) if value == "lists"=>{response_hash["lists"]},
+        Some(value) if value == "use"=>{response_hash["use"]},
+        Some(value) if value == "new-list"=>{response_hash["new-list"]},
+        Some(value) if value == "rename-list"=>{response_hash["rename-list"]},
+        Some(value) if value == "delete-list"=>{response_hash["delete-list"]},
+        Some(value) if value == "move"=>{response_hash["move"]},
+        Some(value) if value == "copy"=>{response_hash["copy"]},
+        Some(value) if value =="exit"=>{response_hash["exit"]},
+        Some(value) if value.is_empty() =>{response_hash["empty_string"]}
+        None=>{response_hash["empty_string"]}
+        _=>"-1"
+    };
+    if help_info == "-1" {
+        return Err("Invalid HELP command please try again.".to_string());
+    }
+    Ok(help_info.to_string())
+}
+
+/// Prints all Tasks in TaskList
+pub fn command_list(global_tasks:&mut TaskList){
+    global_tasks.print_pretty();
+}
+
+/// After a change to the whole list shows it, unless after_change is "none"
+pub fn command_show_list(global_tasks:&mut TaskList){
+    if settings().after_change != "none" {
+        command_list(global_tasks);
+    }
+}
+
+/// After a change shows only the changed Tasks, the whole list when after_change is "list", or nothing with "none"
+pub fn command_show_changed(global_tasks:&mut TaskList,title:&str,indexes:&[usize]){
+    if settings().after_change == "none" {
+        return
+    }
+    if settings().after_change == "list" {
+        command_list(global_tasks);
+        return
+    }
+    global_tasks.print_selection(title,indexes);
+}
+
+/// Confirms a removal with the number and text the Task had
+pub fn command_show_removed(global_tasks:&mut TaskList,index:usize,data:&str){
+    if settings().after_change == "none" {
+        return
+    }
+    if settings().after_change == "list" {
+        command_list(global_tasks);
+        return
+    }
+    let spacing=" ".repeat(settings().indent);
+    println!("{spacing}{} {index}: {}\r\n","Removed".color(palette().label),data.strikethrough());
+}
+
+/// Lists every Task with columns, dates and wrapping chosen by the user
+pub fn command_list_with(global_tasks:&TaskList,options:&ListOptions){
+    let all:Vec<usize>=(0..global_tasks.tasks.len()).collect();
+    global_tasks.print_table("Tasks: ",&all,options);
+}
+
+/// Reads a due date typed by the user, either YYYY-MM-DD or the long form from the date_format setting.
+/// The time part is ignored, due dates are whole days in the user's timezone.
+pub fn parse_due_date(date:&str)->Result<DateTime<Utc>,String>{
+    let default_date_format=settings().date_format.clone();
+    let default_time = NaiveTime::default(); // equivelant to NaiveTime::from_hms_opt(0, 0, 0).unwrap()
+    let parsed_date=NaiveDate::parse_from_str(date.trim(), &default_date_format)
+                                    .or_else(|_| NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d"))
+                                    .map_err(|_| format!("could not read due date '{date}'."))?
+                                    .and_time(default_time);
+    Ok(match Local.from_local_datetime(&parsed_date).earliest(){
+        Some(value)=>value.with_timezone(&Utc),
+        None=>parsed_date.and_utc()
+    })
+}
+
+/// Saves the whole list, a failed save is reported and the change stays in memory
+fn save_all(store:&mut dyn TaskStore,global_tasks:&TaskList){
+    report(try_save(store,global_tasks,None));
+}
+
+/// Saves after only the Tasks at indexes were changed or added
+fn save_changes(store:&mut dyn TaskStore,global_tasks:&TaskList,indexes:&[usize]){
+    report(try_save(store,global_tasks,Some(indexes)));
+}
+
+/// Saves the whole list, or only the Tasks at indexes, and returns the message of a failed save
+fn try_save(store:&mut dyn TaskStore,global_tasks:&TaskList,indexes:Option<&[usize]>)->Vec<String>{
+    let result=match indexes {
+        Some(indexes)=>store.save_changed(global_tasks,indexes),
+        None=>store.save(global_tasks)
+    };
+    result.err().map(|error| format!("Could not save to {}: {error}",store.location())).into_iter().collect()
+}
+
+/// Prints the warnings and notes a command returned instead of printing them itself
+pub fn report(messages:Vec<String>){
+    for message in messages{
+        eprintln!("{message}");
+    }
+}
+
+/// Adds new Task to TaskList, returning what the save had to report
+pub fn command_add(global_tasks:&mut TaskList,data:String,date:String,store:&mut dyn TaskStore)->Result<Vec<String>,String>{
+    let mut temp_task = Task::new(false, data);
+
+    if !date.is_empty() {
+        temp_task.due_date=Some(parse_due_date(&date).map_err(|error| format!("Invalid ADD command, {error}"))?);
+    }else{
+        temp_task.due_date=None;
+    }
+    
+    match global_tasks.add_task(temp_task){
+        Ok(index)=>Ok(try_save(store,global_tasks,Some(&[index]))),
+        Err(_)=>Err("Invalid ADD command please try again.".to_string())
+    }
+}
+
+/// Removes a Task in TaskList by Index
+pub fn command_remove(global_tasks:&mut TaskList,mut index:usize,store:&mut dyn TaskStore)->Result<Vec<String>,String>{
+    index=index.overflowing_sub(1).0;//prevent panic, handle elegantly later
+    if global_tasks.delete_task(index).is_ok() {
+        Ok(try_save(store,global_tasks,None))
+    }else{
+        Err("Invalid REMOVE command please try again.".to_string())
+    }
+}
+
+/// Replaces the text of a Task in TaskList by Index
+pub fn command_edit(global_tasks:&mut TaskList,mut index:usize,data:String,store:&mut dyn TaskStore)->Result<Vec<String>,String>{
+    index=index.overflowing_sub(1).0;//prevent panic, handle elegantly later
+    if data.trim().is_empty() {
+        return Err("Invalid EDIT command, the new text is empty.".to_string())
+    }
+    if global_tasks.set_task_data(index,data.trim().to_string()).is_ok() {
+        Ok(try_save(store,global_tasks,Some(&[index])))
+    }else{
+        Err("Invalid EDIT command please try again.".to_string())
+    }
+}
+
+/// Completes a Task in TaskList by Index
+pub fn command_complete(global_tasks:&mut TaskList,mut index:usize,store:&mut dyn TaskStore)->Result<Vec<String>,String>{
+    index=index.overflowing_sub(1).0;//prevent panic, handle elegantly later
+    if global_tasks.toggle_completed_task(index).is_ok(){
+        let mut changed=vec![index];
+        let mut messages=Vec::new();
+        // this must run before the save
+        if global_tasks.tasks[index].completed{
+            global_tasks.tasks[index].completed_date=Some(Utc::now());
+            let open=global_tasks.open_prerequisites(index);
+            if !open.is_empty() {
+                let open_string=open.iter().map(|i| (i+1).to_string()).collect::<Vec<_>>().join(", ");
+                messages.push(format!("Warning: task {} still has open prerequisites: {}",index+1,open_string));
+            }
+            if let Some(next_index)=global_tasks.spawn_next_recurrence(index) {
+                let next_task=&global_tasks.tasks[next_index];
+                let next_due=next_task.due_date.map(|d| d.with_timezone(&Local).to_string()).unwrap_or_default();
+                messages.push(format!("Logged completion of '{}', next one is task {} due {}.",next_task.data,next_index+1,next_due));
+                changed.push(next_index);
+            }
+        }
+        messages.extend(try_save(store,global_tasks,Some(&changed)));
+        Ok(messages)
+    }else{
+        Err("Invalid COMPLETE command please try again.".to_string())
+    }  
+}
+
+/// Makes a Task depend on another Task by Index
+pub fn command_depend(global_tasks:&mut TaskList,mut index:usize,mut prerequisite:usize,store:&mut dyn TaskStore)->Result<(),String>{
+    index=index.overflowing_sub(1).0;//prevent panic, handle elegantly later
+    prerequisite=prerequisite.overflowing_sub(1).0;
+    match global_tasks.add_dependency(index,prerequisite){
+        Ok(_)=>{
+            save_changes(store,global_tasks,&[index]);
+            Ok(())
+        },
+        Err(error)=>Err(format!("Invalid DEPEND command: {error}"))
+    }
+}
+
+/// Removes a dependency between two Tasks by Index
+pub fn command_undepend(global_tasks:&mut TaskList,mut index:usize,mut prerequisite:usize,store:&mut dyn TaskStore)->Result<(),String>{
+    index=index.overflowing_sub(1).0;//prevent panic, handle elegantly later
+    prerequisite=prerequisite.overflowing_sub(1).0;
+    match global_tasks.remove_dependency(index,prerequisite){
+        Ok(_)=>{
+            save_changes(store,global_tasks,&[index]);
+            Ok(())
+        },
+        Err(error)=>Err(format!("Invalid UNDEPEND command: {error}"))
+    }
+}
+
+/// Sets or clears the recurrence rule of a Task by Index
+pub fn command_recur(global_tasks:&mut TaskList,mut index:usize,rule:String,store:&mut dyn TaskStore)->Result<(),String>{
+    index=index.overflowing_sub(1).0;//prevent panic, handle elegantly later
+    let recurrence=parse_recurrence_rule(&rule).map_err(|error| format!("Invalid RECUR command: {error}"))?;
+    match global_tasks.tasks.get_mut(index){
+        Some(task)=>{
+            task.recurrence=recurrence;
+            global_tasks.touch(index);
+            save_changes(store,global_tasks,&[index]);
+            Ok(())
+        },
+        None=>Err("Invalid RECUR command please try again.".to_string())
+    }
+}
+
+/// Reads a recurrence rule, `none` and `off` clear it
+fn parse_recurrence_rule(rule:&str)->Result<Option<Recurrence>,String>{
+    match rule.trim() {
+        "none" | "off" =>Ok(None),
+        value=>value.parse::<Recurrence>().map(Some)
+    }
+}
+
+/// Appends a note to a Task by Index, or edits all its notes in $EDITOR when text is empty
+pub fn command_note(global_tasks:&mut TaskList,mut index:usize,text:String,store:&mut dyn TaskStore)->Result<(),String>{
+    index=index.overflowing_sub(1).0;//prevent panic, handle elegantly later
+    let task=match global_tasks.tasks.get_mut(index){
+        Some(task)=>task,
+        None=>return Err("Invalid NOTE command please try again.".to_string())
+    };
+    if text.trim().is_empty() {
+        let initial=task.notes.iter().map(|note| format!("{note}\n")).collect::<String>();
+        let edited=edit_text(&initial,".md")?;
+        let mut notes:Vec<String>=edited.lines().map(|line| line.trim_end().to_string()).collect();
+        while notes.last().is_some_and(|line| line.is_empty()) {
+            notes.pop();
+        }
+        task.notes=notes;
+    }else{
+        task.notes.push(text.trim().to_string());
+    }
+    global_tasks.touch(index);
+    save_changes(store,global_tasks,&[index]);
+    Ok(())
+}
+
+/// Prints the detail view of a Task by Index
+pub fn command_show(global_tasks:&mut TaskList,mut index:usize)->Result<(),String>{
+    index=index.overflowing_sub(1).0;//prevent panic, handle elegantly later
+    if index >= global_tasks.tasks.len() {
+        return Err("Invalid SHOW command please try again.".to_string())
+    }
+    global_tasks.print_detail(index);
+    Ok(())
+}
+
+/// Edits one Task by Index, or the whole TaskList when index is None, as Markdown in $EDITOR
+pub fn command_edit_in_editor(global_tasks:&mut TaskList,index:Option<usize>,store:&mut dyn TaskStore)->Result<(),String>{
+    let index=index.map(|i| i.overflowing_sub(1).0);//prevent panic, handle elegantly later
+    let before=match index {
+        Some(i)=>match global_tasks.tasks.get(i){
+            Some(task)=>TaskList{ tasks: vec![task.clone()] },
+            None=>return Err("Invalid EDIT-IN-EDITOR command please try again.".to_string())
+        },
+        None=>global_tasks.clone()
+    };
+
+    let mut text=convert_tltostring(before.clone());
+    let mut edited_list=loop {
+        text=edit_text(&text,".md")?;
+        let problems=match check_edited_text(global_tasks,index,&text) {
+            Ok(parsed)=>break parsed,
+            Err(problems)=>problems
+        };
+        for problem in problems{
+            eprintln!("{problem}");
+        }
+        if prompt("Edit again? [y/N]") != "y" {
+            return Err("EDIT-IN-EDITOR aborted, nothing was changed.".to_string())
+        }
+    };
+
+    let changes=summarize_changes(&before.tasks,&edited_list.tasks);
+    if changes.is_empty() {
+        println!("No changes.");
+        return Ok(())
+    }
+    for change in &changes{
+        println!("{change}");
+    }
+    if prompt("Apply these changes? [y/N]") != "y" {
+        return Err("EDIT-IN-EDITOR aborted, nothing was changed.".to_string())
+    }
+
+    match index {
+        Some(i)=>{
+            global_tasks.tasks[i]=edited_list.tasks.remove(0);
+            global_tasks.touch(i);
+        },
+        None=>{
+            for i in 0..edited_list.tasks.len(){
+                let task=&edited_list.tasks[i];
+                if !before.tasks.iter().any(|t| t == task) {
+                    edited_list.touch(i);
+                }
+            }
+            *global_tasks=edited_list;
+            save_all(store,global_tasks);
+            return Ok(())
+        }
+    }
+    save_changes(store,global_tasks,&index.into_iter().collect::<Vec<_>>());
+    Ok(())
+}
+
+/// Reads the text of an edit-in-editor session, the whole list or the one Task at index.
+/// The list it would leave behind is validated either way, so one Task can not bring in a broken dependency.
+pub fn check_edited_text(global_tasks:&TaskList,index:Option<usize>,text:&str)->Result<TaskList,Vec<String>>{
+    let mut problems:Vec<String>=find_parse_problems(text)
+        .iter()
+        .map(|(n,reason)| format!("Line {n}: {reason}"))
+        .collect();
+    if text.trim().is_empty() {
+        problems.push("The '# TaskList:' header is missing.".to_string());
+    }
+    let mut parsed=convert_stringtotl(text.to_string());
+    match index {
+        Some(_) if parsed.tasks.len() != 1=>{
+            problems.push(format!("Expected exactly one task, found {}.",parsed.tasks.len()));
+        },
+        Some(i)=>{
+            // the edited Task keeps its identity even if the id field was touched
+            parsed.tasks[0].id=global_tasks.tasks[i].id;
+            let mut merged=global_tasks.clone();
+            merged.tasks[i]=parsed.tasks[0].clone();
+            problems.extend(merged.validate());
+        },
+        None=>problems.extend(parsed.validate())
+    }
+    if problems.is_empty() {
+        Ok(parsed)
+    }else{
+        Err(problems)
+    }
+}
+
+/// Sorts the TaskList by a field and saves the new order
+pub fn command_sort(global_tasks:&mut TaskList,field:String,descending:bool,store:&mut dyn TaskStore)->Result<(),String>{
+    global_tasks.sort_by_field(field.trim().to_lowercase().as_str(),descending)
+                .map_err(|error| format!("Invalid SORT command: {error}"))?;
+    save_all(store,global_tasks);
+    Ok(())
+}
+
+/// Prints Tasks whose date field is before or after a date (YYYY-MM-DD) or an age like 30d
+pub fn command_filter(global_tasks:&mut TaskList,field:String,direction:String,when:String)->Result<(),String>{
+    let before=match direction.trim().to_lowercase().as_str() {
+        "before"=>true,
+        "after"=>false,
+        _=>return Err("Invalid FILTER command, use before or after.".to_string())
+    };
+    let when=parse_date_or_age(when.trim()).ok_or("Invalid FILTER command, use a date like 2024-03-01 or an age like 30d.".to_string())?;
+    let matches=global_tasks.filter_by_date(field.trim().to_lowercase().as_str(),before,when)
+                            .map_err(|error| format!("Invalid FILTER command: {error}"))?;
+    global_tasks.print_selection("Filtered: ",&matches);
+    Ok(())
+}
+
+/// Reads YYYY-MM-DD as local midnight, or Nd as N days ago
+pub fn parse_date_or_age(value:&str)->Option<DateTime<Utc>>{
+    if let Some(days)=value.strip_suffix('d').and_then(|d| d.parse::<u64>().ok()) {
+        return Utc::now().checked_sub_days(Days::new(days))
+    }
+    let date=NaiveDate::parse_from_str(value,"%Y-%m-%d").ok()?;
+    Local.from_local_datetime(&date.and_time(NaiveTime::default()))
+         .earliest()
+         .map(|d| d.with_timezone(&Utc))
+}
+
+/// Prints open Tasks grouped into Overdue, Today, Tomorrow, This week, Later and No date
+pub fn command_agenda(global_tasks:&mut TaskList,buckets:&[AgendaBucket]){
+    let groups:Vec<(AgendaBucket,Vec<usize>)>=group_tasks(global_tasks,local_today())
+        .into_iter()
+        .filter(|(bucket,_)| buckets.contains(bucket))
+        .collect();
+    print_agenda(global_tasks,&groups);
+}
+
+/// Prints open Tasks due within the next number of days, like 7d
+pub fn command_upcoming(global_tasks:&mut TaskList,window:String)->Result<(),String>{
+    let days=match window.trim() {
+        ""=>7,
+        value=>value.trim_end_matches('d').parse::<i64>().map_err(|_| "Invalid UPCOMING command, use a number of days like 7d.".to_string())?
+    };
+    let today=local_today();
+    let upcoming=upcoming_tasks(global_tasks,today,days);
+    let groups:Vec<(AgendaBucket,Vec<usize>)>=AgendaBucket::into_iter()
+        .map(|bucket|{
+            let indexes=upcoming.iter()
+                .copied()
+                .filter(|i| AgendaBucket::for_due_date(global_tasks.tasks[*i].due_date,today) == bucket)
+                .collect();
+            (bucket,indexes)
+        })
+        .collect();
+    print_agenda(global_tasks,&groups);
+    Ok(())
+}
+
+/// Prints a month grid (YYYY-MM, current month when empty) or the Tasks due on one day (YYYY-MM-DD)
+pub fn command_calendar(global_tasks:&mut TaskList,when:String)->Result<(),String>{
+    let today=local_today();
+    let when=when.trim();
+    if let Ok(day)=NaiveDate::parse_from_str(when,"%Y-%m-%d") {
+        let due=tasks_on_day(global_tasks,day);
+        global_tasks.print_selection(format!("{}: ",day.format("%A %Y-%m-%d")).as_str(),&due);
+        return Ok(())
+    }
+    let month=match when {
+        ""=>today,
+        value=>NaiveDate::parse_from_str(format!("{value}-01").as_str(),"%Y-%m-%d")
+                         .map_err(|_| "Invalid CALENDAR command, use a month like 2024-03 or a day like 2024-03-15.".to_string())?
+    };
+    println!("\r\n{}",render_month(global_tasks,month,today,week_start()));
+    Ok(())
+}
+
+/// Prints only the Tasks that can be worked on right now
+pub fn command_next(global_tasks:&mut TaskList){
+    let actionable=global_tasks.actionable_tasks();
+    global_tasks.print_selection("Next: ",&actionable);
+}
+
+/// Shows, reads or changes settings, `config set` also saves the config file
+pub fn command_config(arguments:&str)->Result<(),String>{
+    let mut words=arguments.split_whitespace();
+    let action=words.next().unwrap_or("show").to_lowercase();
+    let key=words.next().unwrap_or("");
+    let value=words.collect::<Vec<&str>>().join(" ");
+    let path=|config:&Config| config.path.as_ref().map(|p| p.display().to_string()).unwrap_or("N/A".to_string());
+
+    match action.as_str() {
+        "show"=>{
+            let config=settings();
+            let data=toml::to_string_pretty(config.as_ref()).map_err(|error| error.to_string())?;
+            println!("# {}\r\n{data}",path(&config));
+            Ok(())
+        },
+        "path"=>{
+            println!("{}",path(&settings()));
+            Ok(())
+        },
+        "get" if !key.is_empty()=>{
+            println!("{}",settings().get(key)?);
+            Ok(())
+        },
+        "set" if !key.is_empty() && !value.is_empty()=>{
+            // the file gets only this change, not what command line flags like --plain changed for this run
+            let path=settings().path.clone();
+            let mut stored=load_config(path)?;
+            stored.set(key,&value)?;
+            let saved=stored.save()?;
+            let mut config=Config::clone(&settings());
+            config.set(key,&value)?;
+            install_config(config);
+            let mode=settings().color.clone();
+            apply_color_mode(&mode);
+            println!("Set {key} to {} in {}.",settings().get(key)?,saved.display());
+            Ok(())
+        },
+        _=>Err("Invalid CONFIG command please try again.".to_string())
+    }
+}
+
+/// Splits IMPORT and EXPORT arguments into a format, from `--format=name` or `--format name`, and a file
+pub fn exchange_arguments(args:&CommandArgs)->(Option<String>,String){
+    let mut format=args.get("format").map(|format| format.to_lowercase());
+    // the file is read from the raw text, so a path like C:\data\tasks.csv keeps its backslashes
+    let mut words=args.raw().split_whitespace();
+    let mut path=Vec::new();
+    while let Some(word)=words.next(){
+        if word.eq_ignore_ascii_case("--format") {
+            format=words.next().map(|format| format.to_lowercase());
+        }else if !word.starts_with("--") {
+            path.push(word);
+        }
+    }
+    (format,path_argument(&path.join(" ")))
+}
+
+/// A file path as typed, without the quotes around it if it has any
+pub fn path_argument(text:&str)->String{
+    let text=text.trim();
+    for quote in ['"','\''] {
+        if text.len() > 1 && text.starts_with(quote) && text.ends_with(quote) {
+            return text[1..text.len()-1].to_string()
+        }
+    }
+    text.to_string()
+}
+
+/// Represents the file formats IMPORT and EXPORT understand
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum ExchangeFormat{
+    TodoTxt,
+    ICal,
+    Csv
+}
+
+impl ExchangeFormat{
+    /// Picks the format from its name or else the file extension
+    pub fn from_name(command:&str,format:Option<String>,filepath:&str)->Result<ExchangeFormat,String>{
+        let format=format.or_else(|| Path::new(filepath).extension().map(|e| e.to_string_lossy().to_lowercase()));
+        match format.as_deref() {
+            Some("todotxt" | "todo.txt" | "txt")=>Ok(ExchangeFormat::TodoTxt),
+            Some("ics" | "ical" | "icalendar")=>Ok(ExchangeFormat::ICal),
+            Some("csv")=>Ok(ExchangeFormat::Csv),
+            Some(other)=>Err(format!("Invalid {command} command, '{other}' is not a format, use --format=todotxt, ics or csv.")),
+            None=>Err(format!("Invalid {command} command, choose a format with --format=todotxt, ics or csv."))
+        }
+    }
+
+    /// Reads Tasks, along with the rows that could not be read
+    pub fn read(&self,data:&str)->Result<(TaskList,Vec<String>),String>{
+        match self {
+            ExchangeFormat::TodoTxt=>Ok((convert_todotxttotl(data),Vec::new())),
+            ExchangeFormat::ICal=>Ok((convert_icaltotl(data)?,Vec::new())),
+            ExchangeFormat::Csv=>convert_csvtotl(data)
+        }
+    }
+
+    pub fn write(&self,tl:&TaskList)->String{
+        match self {
+            ExchangeFormat::TodoTxt=>convert_tltotodotxt(tl),
+            ExchangeFormat::ICal=>convert_tltoical(tl),
+            ExchangeFormat::Csv=>convert_tltocsv(tl)
+        }
+    }
+}
+
+/// Reads the Tasks of a file in another format, with the rows that could not be read
+fn read_import(format:Option<String>,filepath:&str)->Result<(TaskList,Vec<String>),String>{
+    if filepath.is_empty() {
+        return Err("Invalid IMPORT command please try again.".to_string())
+    }
+    let format=ExchangeFormat::from_name("IMPORT",format,filepath)?;
+    let data=std::fs::read_to_string(filepath).map_err(|error| format!("Invalid IMPORT command, could not read {filepath}: {error}"))?;
+    format.read(&data).map_err(|error| format!("Invalid IMPORT command, {filepath}: {error}"))
+}
+
+/// True when IMPORT should replace the list, from --replace or --append or else the import_mode setting
+pub fn import_replaces(args:&CommandArgs)->bool{
+    match (args.has_flag("replace"),args.has_flag("append")) {
+        (true,_)=>true,
+        (_,true)=>false,
+        _=>settings().import_mode == "replace"
+    }
+}
+
+/// Adds, or with replace swaps in, the Tasks from another format's file, returning their indexes.
+/// Nothing is imported when any row can not be read.
+pub fn command_import(global_tasks:&mut TaskList,format:Option<String>,filepath:String,replace:bool,store:&mut dyn TaskStore)->Result<Vec<usize>,String>{
+    let (imported,problems)=read_import(format,&filepath)?;
+    if !problems.is_empty() {
+        return Err(format!("Invalid IMPORT command, nothing was imported because {} rows of {filepath} could not be read:\r\n{}\r\nUse --dry-run to see what would be imported.",
+            problems.len(),problems.join("\r\n")))
+    }
+    let indexes=merge_import(global_tasks,imported,replace)?;
+    if replace {
+        save_all(store,global_tasks);
+    }else{
+        save_changes(store,global_tasks,&indexes);
+    }
+    Ok(indexes)
+}
+
+/// Puts imported Tasks into the list and returns their indexes. The list is left as it was
+/// when the Tasks would make it inconsistent, like with a dependency cycle among them.
+fn merge_import(global_tasks:&mut TaskList,imported:TaskList,replace:bool)->Result<Vec<usize>,String>{
+    let mut merged=if replace { TaskList{ tasks:Vec::new() } } else { global_tasks.clone() };
+    // ids already in use are given new ones, so dependencies are pointed at the new ids
+    let mut ids:HashMap<usize,usize>=HashMap::new();
+    let mut indexes:Vec<usize>=Vec::new();
+    let mut added:Vec<usize>=Vec::new();
+    for task in imported.tasks{
+        let (id,updated)=(task.id,task.updated_at);
+        // a calendar task exported from here, or imported before, updates the Task it stands for
+        let index=match import_match(&merged,&task) {
+            Some(index)=>{
+                update_from_import(&mut merged.tasks[index],task);
+                index
+            },
+            None=>{
+                let index=merged.add_task(task)?;
+                added.push(index);
+                index
+            }
+        };
+        if updated.is_some() {
+            merged.tasks[index].updated_at=updated;
+        }
+        ids.insert(id,merged.tasks[index].id);
+        if !indexes.contains(&index) {
+            indexes.push(index);
+        }
+    }
+    for index in added.iter(){
+        let task=&mut merged.tasks[*index];
+        task.depends_on=task.depends_on.iter().filter_map(|id| ids.get(id).copied()).collect();
+    }
+    // problems the list already had are not the import's doing
+    let existing=if replace { Vec::new() } else { global_tasks.validate() };
+    let problems:Vec<String>=merged.validate().into_iter().filter(|problem| !existing.contains(problem)).collect();
+    if !problems.is_empty() {
+        return Err(format!("Invalid IMPORT command, nothing was imported because the list would not be consistent:\r\n{}",problems.join("\r\n")))
+    }
+    *global_tasks=merged;
+    Ok(indexes)
+}
+
+/// The Task an imported one stands for, by the calendar UID they share
+fn import_match(global_tasks:&TaskList,task:&Task)->Option<usize>{
+    let uid=task.uid.as_ref()?;
+    global_tasks.tasks.iter().position(|existing| task_uid(existing) == *uid)
+}
+
+/// Takes what a calendar may have changed from an imported Task, keeping the id, dependencies and recurrence
+fn update_from_import(existing:&mut Task,imported:Task){
+    existing.data=imported.data;
+    existing.notes=imported.notes;
+    existing.due_date=imported.due_date;
+    existing.completed=imported.completed;
+    existing.completed_date=imported.completed_date;
+    existing.priority=imported.priority;
+    existing.uid=imported.uid;
+    existing.updated_at=Some(Utc::now());
+}
+
+/// Shows what IMPORT would do and which rows it could not read, without changing anything
+pub fn command_import_preview(global_tasks:&TaskList,format:Option<String>,filepath:String,replace:bool)->Result<(),String>{
+    let (imported,problems)=read_import(format,&filepath)?;
+    let all:Vec<usize>=(0..imported.tasks.len()).collect();
+    let updates=imported.tasks.iter().filter(|task| import_match(global_tasks,task).is_some()).count();
+    let effect=match (replace,updates) {
+        (true,_)=>format!("replacing the {} tasks in the list",global_tasks.tasks.len()),
+        (false,0)=>"added to the list".to_string(),
+        (false,_)=>format!("{updates} of them updating the tasks with the same UID and the others added to the list")
+    };
+    imported.print_selection(&format!("Would import {} tasks from {filepath}, {effect}: ",all.len()),&all);
+    let spacing=" ".repeat(settings().indent);
+    if !problems.is_empty() {
+        println!("{spacing}{}","These rows could not be read, so nothing would be imported:".color(palette().overdue));
+        for problem in problems.iter(){
+            println!("{spacing}{problem}");
+        }
+        println!();
+    }else if let Err(error)=merge_import(&mut global_tasks.clone(),imported,replace) {
+        println!("{spacing}{}\r\n",error.color(palette().overdue));
+    }
+    Ok(())
+}
+
+/// Writes every Task in another format, to a file or else to the screen
+pub fn command_export(global_tasks:&TaskList,format:Option<String>,filepath:String)->Result<(),String>{
+    let data=ExchangeFormat::from_name("EXPORT",format,&filepath)?.write(global_tasks);
+    if filepath.is_empty() {
+        print!("{data}");
+        return Ok(())
+    }
+    export_textfile(&filepath,&data).map_err(|error| format!("Invalid EXPORT command, could not write {filepath}: {error}"))?;
+    println!("Exported {} tasks to {filepath}.",global_tasks.tasks.len());
+    Ok(())
+}
+
+/// Writes a task list read from source in the storage format of the target's extension, then reads it back
+/// to check nothing was lost. Returns the store of the target with the list it holds.
+pub fn command_convert(tl:&TaskList,source:&str,target:String)->Result<(Box<dyn TaskStore>,TaskList),String>{
+    if target.is_empty() {
+        return Err("Invalid CONVERT command please try again.".to_string())
+    }
+    if Path::new(&target).exists() {
+        return Err(format!("Invalid CONVERT command, {target} already exists, convert to a new file."))
+    }
+    save_tltofile(target.clone(),tl.clone()).map_err(|error| format!("Invalid CONVERT command, could not write {target}: {error}"))?;
+    let (target_store,converted)=open_store(&target).map_err(|error| format!("Invalid CONVERT command: {error}"))?;
+    if converted.tasks.len() != tl.tasks.len() {
+        return Err(format!("Invalid CONVERT command, only {} of {} tasks could be read back from {target}.",converted.tasks.len(),tl.tasks.len()))
+    }
+    println!("Converted {} tasks from {} to {} in {target}.",tl.tasks.len(),storage_format_for(source),storage_format_for(&target));
+    Ok((target_store,converted))
+}
+
+/// Lists every line of a task list file that can not be read, with the reason, and changes nothing
+pub fn command_check(filepath:String)->Result<(),String>{
+    // an SQLite database has no lines, it either opens or it does not
+    if storage_format_for(&filepath) == "sqlite" {
+        let tl=try_load_tlfromfile(&filepath).map_err(|error| format!("Invalid CHECK command: {error}"))?;
+        println!("{filepath} reads cleanly, {} tasks.",tl.tasks.len());
+        return Ok(())
+    }
+    let data=std::fs::read_to_string(&filepath).map_err(|error| format!("Invalid CHECK command, could not read {filepath}: {error}"))?;
+    let serializer=serializer_for(&filepath);
+    let problems=serializer.problems(&data);
+    if problems.is_empty() {
+        let tl=serializer.deserialize(&data).map_err(|error| format!("{filepath} can not be read: {error}"))?;
+        println!("{filepath} reads cleanly, {} tasks.",tl.tasks.len());
+        return Ok(())
+    }
+    println!("{}",format_parse_problems(&problems));
+    Err(format!("{filepath} has {} problems, it is not saved until they are fixed.",problems.len()))
+}
+
+/// Prints every list with how many of its Tasks are open, the active list is marked with *
+pub fn command_lists(global_tasks:&TaskList,store:&dyn TaskStore,workspace:&Workspace){
+    let active=workspace.list_name_for(&store.location());
+    let names=workspace.list_names();
+    let width=names.iter().map(|name| name.chars().count()).max().unwrap_or(0);
+    let spacing=" ".repeat(settings().indent);
+    let counts=|tl:&TaskList| format!("{} open, {} tasks",tl.tasks.iter().filter(|t| !t.completed).count(),tl.tasks.len());
+    println!("{spacing}{}","Lists: ".color(palette().label));
+    for name in names{
+        let (marker,summary)=if name == active {
+            ("*",counts(global_tasks))
+        }else{
+            (" ",try_load_tlfromfile(&workspace.list_path(&name)).map(|tl| counts(&tl)).unwrap_or_else(|error| error.to_string()))
+        };
+        println!("{spacing}{marker} {name:<width$}  {}",summary.color(palette().muted));
+    }
+    println!();
+}
+
+/// Switches to a named list and remembers it as the active list for the next run
+pub fn command_use(global_tasks:&mut TaskList,store:&mut Box<dyn TaskStore>,workspace:&Workspace,name:&str)->Result<(),String>{
+    validate_list_name(name).map_err(|error| format!("Invalid USE command, {error}."))?;
+    if !workspace.list_exists(name) {
+        return Err(format!("Invalid USE command, there is no list '{name}', create it with: new-list {name}"))
+    }
+    let (opened,tasks)=open_store(&workspace.list_path(name)).map_err(|error| format!("Invalid USE command: {error}"))?;
+    *store=opened;
+    *global_tasks=tasks;
+    if let Err(error)=workspace.remember_active_list(name) {
+        eprintln!("Could not remember the active list: {error}");
+    }
+    Ok(())
+}
+
+/// Creates an empty named list and switches to it
+pub fn command_new_list(global_tasks:&mut TaskList,store:&mut Box<dyn TaskStore>,workspace:&Workspace,name:&str)->Result<(),String>{
+    validate_list_name(name).map_err(|error| format!("Invalid NEW-LIST command, {error}."))?;
+    if workspace.list_exists(name) {
+        return Err(format!("Invalid NEW-LIST command, the list '{name}' already exists."))
+    }
+    let path=workspace.list_path(name);
+    store_for(&path).and_then(|mut new_store| new_store.save(&TaskList{ tasks:Vec::new() }))
+        .map_err(|error| format!("Invalid NEW-LIST command, could not create {path}: {error}"))?;
+    command_use(global_tasks,store,workspace,name)
+}
+
+/// Renames the file of a list, the active list stays active under its new name
+pub fn command_rename_list(store:&mut Box<dyn TaskStore>,workspace:&Workspace,old:&str,new:&str)->Result<(),String>{
+    for name in [old,new]{
+        validate_list_name(name).map_err(|error| format!("Invalid RENAME-LIST command, {error}."))?;
+    }
+    if old == DEFAULT_LIST || new == DEFAULT_LIST {
+        return Err(format!("Invalid RENAME-LIST command, the {DEFAULT_LIST} list is the data_file setting and keeps its name."))
+    }
+    if !workspace.list_exists(old) {
+        return Err(format!("Invalid RENAME-LIST command, there is no list '{old}'."))
+    }
+    if workspace.list_exists(new) {
+        return Err(format!("Invalid RENAME-LIST command, the list '{new}' already exists."))
+    }
+    let from=workspace.list_path(old);
+    let extension=Path::new(&from).extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+    let to=format!("{}/{new}.{extension}",workspace.lists_dir);
+    std::fs::rename(&from,&to).map_err(|error| format!("Invalid RENAME-LIST command, could not rename {from}: {error}"))?;
+    if store.location() == from {
+        *store=store_for(&to).map_err(|error| format!("Invalid RENAME-LIST command, could not open {to}: {error}"))?;
+        if let Err(error)=workspace.remember_active_list(new) {
+            eprintln!("Could not remember the active list: {error}");
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the file of a list. The active list is refused, and so is a list that still has Tasks unless forced.
+pub fn command_delete_list(store:&dyn TaskStore,workspace:&Workspace,name:&str,force:bool)->Result<(),String>{
+    validate_list_name(name).map_err(|error| format!("Invalid DELETE-LIST command, {error}."))?;
+    if name == DEFAULT_LIST {
+        return Err(format!("Invalid DELETE-LIST command, the {DEFAULT_LIST} list can not be deleted."))
+    }
+    if !workspace.list_exists(name) {
+        return Err(format!("Invalid DELETE-LIST command, there is no list '{name}'."))
+    }
+    let path=workspace.list_path(name);
+    if store.location() == path {
+        return Err(format!("Invalid DELETE-LIST command, '{name}' is the active list, switch to another one first."))
+    }
+    if !force {
+        let tl=try_load_tlfromfile(&path).map_err(|error| format!("Invalid DELETE-LIST command: {error}"))?;
+        if !tl.tasks.is_empty() {
+            return Err(format!("Invalid DELETE-LIST command, '{name}' still has {} tasks, add --force to delete it anyway.",tl.tasks.len()))
+        }
+    }
+    std::fs::remove_file(&path).map_err(|error| format!("Invalid DELETE-LIST command, could not delete {path}: {error}"))
+}
+
+/// Moves, or with copy copies, Tasks by Index to another named list, returning how many.
+/// They get new ids there and keep the dependencies they have on each other.
+pub fn command_move_tasks(global_tasks:&mut TaskList,store:&mut dyn TaskStore,workspace:&Workspace,indexes:&[usize],name:&str,copy:bool)->Result<usize,String>{
+    let command=if copy { "COPY" } else { "MOVE" };
+    validate_list_name(name).map_err(|error| format!("Invalid {command} command, {error}."))?;
+    if !workspace.list_exists(name) {
+        return Err(format!("Invalid {command} command, there is no list '{name}', create it with: new-list {name}"))
+    }
+    let path=workspace.list_path(name);
+    if store.location() == path {
+        return Err(format!("Invalid {command} command, '{name}' is the active list."))
+    }
+    let mut indexes:Vec<usize>=indexes.iter().map(|i| i.overflowing_sub(1).0).collect();//prevent panic, handle elegantly later
+    indexes.sort();
+    indexes.dedup();
+    if indexes.is_empty() || indexes.iter().any(|i| *i >= global_tasks.tasks.len()) {
+        return Err(format!("Invalid {command} command please try again."))
+    }
+
+    let (mut target_store,mut target)=open_store(&path).map_err(|error| format!("Invalid {command} command: {error}"))?;
+    let mut ids:HashMap<usize,usize>=HashMap::new();
+    let mut added:Vec<usize>=Vec::new();
+    for index in indexes.iter(){
+        let task=global_tasks.tasks[*index].clone();
+        let (id,updated)=(task.id,task.updated_at);
+        let added_index=target.add_task(task)?;
+        target.tasks[added_index].updated_at=updated;
+        ids.insert(id,target.tasks[added_index].id);
+        added.push(added_index);
+    }
+    for index in added.iter(){
+        let task=&mut target.tasks[*index];
+        task.depends_on=task.depends_on.iter().filter_map(|id| ids.get(id).copied()).collect();
+    }
+    // the Tasks leave this list only once the other list has them
+    target_store.save_changed(&target,&added).map_err(|error| format!("Invalid {command} command, could not save {path}: {error}"))?;
+    if !copy {
+        for index in indexes.iter().rev(){
+            global_tasks.delete_task(*index)?;
+        }
+        save_all(store,global_tasks);
+    }
+    Ok(indexes.len())
+}
+
+/// Ends the process and exits to terminal
+pub fn command_exit(){
+    std::process::exit(0);
+}
+
+/// Short names that always work, alongside unambiguous prefixes and the aliases in the config file
+pub const BUILTIN_ALIASES:[(&str,&str);5]=[("ls","list"),("rm","remove"),("q","exit"),("quit","exit"),("lint","check")];
+
+/// Replaces an alias at the start of the input with the command text it stands for.
+/// User aliases win over the built-in ones, and the expansion is not expanded again.
+pub fn expand_alias(input:&str,aliases:&HashMap<String,String>)->String{
+    let input=input.trim_start();
+    let (name,rest)=input.split_once(char::is_whitespace).unwrap_or((input,""));
+    let lowered=name.to_lowercase();
+    let expansion=match aliases.get(&lowered) {
+        Some(expansion)=>expansion.trim(),
+        None=>match BUILTIN_ALIASES.iter().find(|(alias,_)| *alias == lowered) {
+            Some((_,command))=>command,
+            None=>return input.to_string()
+        }
+    };
+    if rest.trim().is_empty() {
+        expansion.to_string()
+    }else{
+        format!("{expansion} {}",rest.trim_start())
+    }
+}
+
+/// Finds the command for a typed name, either its full name or an unambiguous prefix.
+/// Anything else is an error naming the candidates or the closest command.
+pub fn resolve_command(name:&str)->Result<TASKCOM,String>{
+    let upper=name.to_uppercase();
+    if let Ok(command)=TASKCOM::from_str(&upper) {
+        return Ok(command)
+    }
+    let matches:Vec<TASKCOM>=TASKCOM::into_iter_client()
+        .filter(|c| !upper.is_empty() && c.to_string().starts_with(&upper))
+        .collect();
+    match matches.as_slice() {
+        [command]=>Ok(command.clone()),
+        [_,..]=>{
+            let names:Vec<String>=matches.iter().map(|c| c.to_string().to_lowercase()).collect();
+            Err(format!("Command '{name}' is ambiguous, it could be: {}.",names.join(", ")))
+        },
+        []=>{
+            let lowered=name.to_lowercase();
+            let closest=TASKCOM::into_iter_client()
+                .map(|c| c.to_string().to_lowercase())
+                .map(|c| (edit_distance(&lowered,&c),c))
+                .min_by_key(|(distance,_)| *distance);
+            match closest {
+                Some((distance,command)) if distance <= 2.max(lowered.len()/3)=>Err(format!("Unknown command '{name}', did you mean '{command}'?")),
+                _=>Err(format!("Unknown command '{name}'. Try 'help' for a list of commands."))
+            }
+        }
+    }
+}
+
+/// Number of single character inserts, deletes and substitutions to turn `a` into `b`
+pub fn edit_distance(a:&str,b:&str)->usize{
+    let b:Vec<char>=b.chars().collect();
+    let mut previous:Vec<usize>=(0..=b.len()).collect();
+    for (i,ca) in a.chars().enumerate(){
+        let mut current=vec![i+1];
+        for (j,cb) in b.iter().enumerate(){
+            let substitution=previous[j]+usize::from(ca != *cb);
+            current.push(substitution.min(previous[j+1]+1).min(current[j]+1));
+        }
+        previous=current;
+    }
+    previous[b.len()]
+}
+
+/// Parses user input into command and arguments
+pub fn parse_input_commands(input: &str) -> (String, CommandArgs){
+    let mut parts = input.trim_start().splitn(2, char::is_whitespace);
+
+    // Parse command
+    let command = parts.next().unwrap_or("").to_lowercase();
+
+    // Parse arguments, there is always at least one (possibly empty) positional argument
+    let arguments = tokenize_arguments(parts.next().unwrap_or(""));
+
+    (command, arguments)
+}
+
+/// Supports the old `add text,date` form: a last comma argument that reads as a date is the due date.
+/// Anything else, like `add Buy eggs, milk, bread`, is all task text.
+fn split_deprecated_due_date(args:&CommandArgs)->(String,String){
+    if let [text @ .., date] = args.positional.as_slice() {
+        if !text.is_empty() && parse_due_date(date).is_ok() {
+            eprintln!("Warning: 'add text,date' is deprecated and will be removed, use 'add text due:YYYY-MM-DD' instead.");
+            return (text.join(", "),date.to_string())
+        }
+    }
+    (args.text(),"".to_string())
+}
+
+/// Turns one line of input into the command to run and its arguments, expanding aliases first
+pub fn parse_command_line(input:&str,aliases:&HashMap<String,String>)->Result<(TASKCOM,CommandArgs),String>{
+    let input=expand_alias(input,aliases);
+    let (command,arguments)=parse_input_commands(&input);
+    Ok((resolve_command(&command)?,arguments))
+}
+
+/// The words of a command, split at commas and spaces, like `move 2,5 home` or `rename-list a b`
+fn list_arguments(args:&CommandArgs)->Vec<String>{
+    args.text().split(|c:char| c == ',' || c.is_whitespace()).filter(|word| !word.is_empty()).map(|word| word.to_string()).collect()
+}
+
+/// Converts command struct into function calls to run command
+pub fn handle_command(command:TASKCOM,args:CommandArgs,global_tasks:&mut TaskList,store:&mut dyn TaskStore)->Result<(),String>{
+    let arguments=&args.positional;
+    match command{
+        TASKCOM::Help=>{
+            // 'help comp' or 'help rm' shows the help of the command they stand for
+            let topic=arguments.first().map(|s| s.trim().to_string()).map(|topic|{
+                let (name,_)=parse_input_commands(&expand_alias(&topic,&HashMap::new()));
+                resolve_command(&name).map(|c| c.to_string().to_lowercase()).unwrap_or(topic)
+            });
+            let help = match command_help(topic){
+                Ok(text)=>{text},
+                Err(error)=>{error}
+            };
+            println!("{}",help);
+            Ok(())
+        },
+        TASKCOM::List=>{
+            command_list_with(global_tasks,&ListOptions::from_args(&args)?);
+            Ok(())
+        },
+        TASKCOM::Add=>{
+            let (data,date)=match args.get("due") {
+                Some(date)=>(args.text(),date.to_string()),
+                None=>split_deprecated_due_date(&args)
+            };
+            // a bad rule stops the add before anything is saved
+            if let Some(rule)=args.get("recur") {
+                parse_recurrence_rule(rule).map_err(|error| format!("Invalid ADD command: {error}"))?;
+            }
+            report(command_add(global_tasks,data,date,store)?);
+            if let Some(rule)=args.get("recur") {
+                command_recur(global_tasks,global_tasks.tasks.len(),rule.to_string(),store)?;
+            }
+            command_show_changed(global_tasks,"Added: ",&[global_tasks.tasks.len()-1]);
+            Ok(())
+        },
+        TASKCOM::Remove=>{
+            let index=match arguments[0].parse::<usize>() {
+                Ok(index)=>{index},
+                Err(_e)=>{return Err("Invalid REMOVE command please try again.".to_string())}
+            };
+            let data=global_tasks.tasks.get(index.wrapping_sub(1)).map(|t| t.data.clone()).unwrap_or_default();
+            report(command_remove(global_tasks,index,store)?);
+            command_show_removed(global_tasks,index,&data);
+            Ok(())
+        },
+        TASKCOM::Complete=>{
+            let index=match arguments[0].parse::<usize>() {
+                Ok(index)=>{index},
+                Err(_e)=>{return Err("Invalid COMPLETE command please try again.".to_string())}
+            };
+            let count=global_tasks.tasks.len();
+            report(command_complete(global_tasks,index,store)?);
+            // a repeating Task also shows the next one it added
+            let changed:Vec<usize>=std::iter::once(index-1).chain(count..global_tasks.tasks.len()).collect();
+            command_show_changed(global_tasks,"Completed: ",&changed);
+            Ok(())
+        },
+        TASKCOM::Depend|TASKCOM::Undepend=>{
+            let indexes:Vec<usize>=arguments.iter().filter_map(|arg| arg.parse::<usize>().ok()).collect();
+            if indexes.len() != 2 || arguments.len() != 2 {
+                return Err(format!("Invalid {command} command please try again."))
+            }
+            match command {
+                TASKCOM::Depend=>command_depend(global_tasks,indexes[0],indexes[1],store)?,
+                _=>command_undepend(global_tasks,indexes[0],indexes[1],store)?
+            }
+            command_show_changed(global_tasks,"Updated: ",&[indexes[0]-1,indexes[1]-1]);
+            Ok(())
+        },
+        TASKCOM::Next=>{
+            command_next(global_tasks);
+            Ok(())
+        },
+        TASKCOM::Recur=>{
+            let index=match arguments[0].parse::<usize>() {
+                Ok(index)=>{index},
+                Err(_e)=>{return Err("Invalid RECUR command please try again.".to_string())}
+            };
+            // weekday lists contain commas, so everything after the index is the rule
+            let rule=args.text_after_first();
+            command_recur(global_tasks,index,rule,store)?;
+            command_show_changed(global_tasks,"Updated: ",&[index-1]);
+            Ok(())
+        },
+        TASKCOM::Note=>{
+            let index=match arguments[0].parse::<usize>() {
+                Ok(index)=>{index},
+                Err(_e)=>{return Err("Invalid NOTE command please try again.".to_string())}
+            };
+            // notes may contain commas, so everything after the index is the text
+            let text=args.text_after_first();
+            command_note(global_tasks,index,text,store)?;
+            command_show(global_tasks,index)
+        },
+        TASKCOM::Show=>{
+            let index=match arguments[0].parse::<usize>() {
+                Ok(index)=>{index},
+                Err(_e)=>{return Err("Invalid SHOW command please try again.".to_string())}
+            };
+            command_show(global_tasks,index)
+        },
+        TASKCOM::EditInEditor=>{
+            let index=match arguments[0].as_str() {
+                ""=>None,
+                value=>match value.parse::<usize>() {
+                    Ok(index)=>Some(index),
+                    Err(_e)=>{return Err("Invalid EDIT-IN-EDITOR command please try again.".to_string())}
+                }
+            };
+            command_edit_in_editor(global_tasks,index,store)?;
+            match index {
+                Some(index) if index <= global_tasks.tasks.len()=>command_show_changed(global_tasks,"Updated: ",&[index-1]),
+                _=>command_show_list(global_tasks)
+            }
+            Ok(())
+        },
+        TASKCOM::Sort=>{
+            let descending=args.has_flag("desc") || arguments.get(1).is_some_and(|arg| arg.eq_ignore_ascii_case("desc"));
+            command_sort(global_tasks,arguments[0].to_string(),descending,store)?;
+            command_show_list(global_tasks);
+            Ok(())
+        },
+        TASKCOM::Filter=>{
+            if arguments.len() != 3 {
+                return Err("Invalid FILTER command please try again.".to_string())
+            }
+            command_filter(global_tasks,arguments[0].to_string(),arguments[1].to_string(),arguments[2].to_string())
+        },
+        TASKCOM::Agenda=>{
+            command_agenda(global_tasks,&AgendaBucket::into_iter().collect::<Vec<_>>());
+            Ok(())
+        },
+        TASKCOM::Today=>{
+            command_agenda(global_tasks,&[AgendaBucket::Overdue,AgendaBucket::Today]);
+            Ok(())
+        },
+        TASKCOM::Overdue=>{
+            command_agenda(global_tasks,&[AgendaBucket::Overdue]);
+            Ok(())
+        },
+        TASKCOM::Upcoming=>command_upcoming(global_tasks,arguments[0].to_string()),
+        TASKCOM::Calendar=>command_calendar(global_tasks,arguments[0].to_string()),
+        TASKCOM::Edit=>{
+            let index=match arguments[0].parse::<usize>() {
+                Ok(index)=>{index},
+                Err(_e)=>{return Err("Invalid EDIT command please try again.".to_string())}
+            };
+            // task text may contain commas, so everything after the index is the text
+            report(command_edit(global_tasks,index,args.text_after_first(),store)?);
+            command_show_changed(global_tasks,"Updated: ",&[index-1]);
+            Ok(())
+        },
+        TASKCOM::Tui=>{
+            run_tui(global_tasks,store).map_err(|error| format!("The TUI failed: {error}"))?;
+            command_list(global_tasks);
+            Ok(())
+        },
+        TASKCOM::Config=>command_config(&args.text()),
+        TASKCOM::Import=>{
+            let (format,filepath)=exchange_arguments(&args);
+            let replace=import_replaces(&args);
+            if args.has_flag("dry-run") {
+                return command_import_preview(global_tasks,format,filepath,replace)
+            }
+            let indexes=command_import(global_tasks,format,filepath.clone(),replace,store)?;
+            println!("{} {} tasks from {filepath}.",if replace { "Replaced the list with" } else { "Imported" },indexes.len());
+            command_show_changed(global_tasks,"Imported: ",&indexes);
+            Ok(())
+        },
+        TASKCOM::Export=>{
+            let (format,filepath)=exchange_arguments(&args);
+            command_export(global_tasks,format,filepath)
+        },
+        TASKCOM::Check=>{
+            let filepath=path_argument(args.raw());
+            command_check(if filepath.is_empty() { store.location() } else { filepath })
+        },
+        TASKCOM::Lists=>{
+            command_lists(global_tasks,&*store,&Workspace::from_settings());
+            Ok(())
+        },
+        // these open or change other lists, which a batch can not hold back until it ends
+        TASKCOM::Load | TASKCOM::SaveAs | TASKCOM::Convert | TASKCOM::Use | TASKCOM::NewList | TASKCOM::RenameList
+            | TASKCOM::DeleteList | TASKCOM::Move | TASKCOM::Copy=>{
+            Err(format!("The {} command works on other lists, it is not available in batch mode.",command.to_string().to_lowercase()))
+        },
+        TASKCOM::Exit=>{
+            command_exit();
+            Ok(())
+        },
+        TASKCOM::Unknown=> Err("Invalid command. Try 'help' for a list of commands.".to_string())
+    }
+}
+
+/// Runs a command typed at the prompt, where load, use and the other commands that work on other lists
+/// may replace the store. Every other command goes to handle_command.
+pub fn run_command(command:TASKCOM,args:CommandArgs,global_tasks:&mut TaskList,store:&mut Box<dyn TaskStore>)->Result<(),String>{
+    match command{
+        TASKCOM::Load=>{
+            command_loadfile(global_tasks,store,path_argument(args.raw()))
+                .map_err(|error| format!("Invalid LOAD command: {error}"))?;
+            command_list(global_tasks);
+            Ok(())
+        },
+        TASKCOM::SaveAs=>{
+            command_savefile_as(global_tasks,store,path_argument(args.raw()))
+                .map_err(|error| format!("Invalid SAVEAS command: {error}"))?;
+            println!("Saved to {}, it is now the default file.",store.location());
+            Ok(())
+        },
+        TASKCOM::Convert=>{
+            let files:Vec<String>=args.raw().split_whitespace().map(path_argument).collect();
+            match files.as_slice() {
+                [target]=>{
+                    (*store,*global_tasks)=command_convert(global_tasks,&store.location(),target.clone())?;
+                    println!("{target} is now the default file.");
+                    Ok(())
+                },
+                [source,target]=>{
+                    let tl=try_load_tlfromfile(source).map_err(|error| format!("Invalid CONVERT command: {error}"))?;
+                    command_convert(&tl,source,target.clone()).map(|_| ())
+                },
+                _=>Err("Invalid CONVERT command please try again.".to_string())
+            }
+        },
+        TASKCOM::Use=>{
+            command_use(global_tasks,store,&Workspace::from_settings(),args.text().trim())?;
+            command_list(global_tasks);
+            Ok(())
+        },
+        TASKCOM::NewList=>{
+            let name=args.text().trim().to_string();
+            command_new_list(global_tasks,store,&Workspace::from_settings(),&name)?;
+            println!("Created the list '{name}', it is now the active list.");
+            Ok(())
+        },
+        TASKCOM::RenameList=>{
+            match list_arguments(&args).as_slice() {
+                [old,new]=>{
+                    command_rename_list(store,&Workspace::from_settings(),old,new)?;
+                    println!("Renamed the list '{old}' to '{new}'.");
+                    Ok(())
+                },
+                _=>Err("Invalid RENAME-LIST command please try again.".to_string())
+            }
+        },
+        TASKCOM::DeleteList=>{
+            let name=args.text().trim().to_string();
+            command_delete_list(store.as_ref(),&Workspace::from_settings(),&name,args.has_flag("force"))?;
+            println!("Deleted the list '{name}'.");
+            Ok(())
+        },
+        TASKCOM::Move | TASKCOM::Copy=>{
+            let copy=matches!(command,TASKCOM::Copy);
+            let words=list_arguments(&args);
+            let Some((name,indexes))=words.split_last() else {
+                return Err(format!("Invalid {command} command please try again."))
+            };
+            let indexes:Vec<usize>=indexes.iter().map(|i| i.parse::<usize>()).collect::<Result<_,_>>()
+                .map_err(|_| format!("Invalid {command} command please try again."))?;
+            let count=command_move_tasks(global_tasks,store.as_mut(),&Workspace::from_settings(),&indexes,name,copy)?;
+            println!("{} {count} tasks to '{name}'.",if copy { "Copied" } else { "Moved" });
+            if !copy {
+                command_list(global_tasks);
+            }
+            Ok(())
+        },
+        _=>handle_command(command,args,global_tasks,store.as_mut())
+    }
+}
+
+/// allows user to load a tasklist file
+pub fn command_loadfile(global_tasks:&mut TaskList,store:&mut Box<dyn TaskStore>,filepath:String)->Result<(),Error>{
+    // validate shape of filepath is a filepath
+    let validate_filepath = Path::new(&filepath).parent();
+    // validate the filepath exists
+    match validate_filepath {
+        Some(_)=>{},
+        None=>{return Err(Error::other("Invalid filepath provided for command_loadfile."))}
+    }
+    // a file that does not parse leaves the current list and default file alone
+    let (loaded_store,loaded)=open_store(&filepath)?;
+    *store=loaded_store;
+    *global_tasks=loaded;
+    Ok(())
+}
+
+/// allows user to save a tasklist file
+pub fn command_savefile_as(global_tasks:&mut TaskList,store:&mut Box<dyn TaskStore>,filepath:String)->Result<(),Error>{
+    // validate shape of filepath is a filepath
+    let validate_filepath = Path::new(&filepath).parent();
+    // validate the filepath exists
+    match validate_filepath {
+        Some(_)=>{},
+        None=>{return Err(Error::other("Invalid filepath provided for command_saveas."))}
+    }
+    // save to filepath
+    let _ = save_tltofile(filepath.clone(), global_tasks.clone())?;
+
+    // re-load from the filepath we saved to and keep using its store, just like command_load
+    let (saved_store,saved)=open_store(&filepath)?;
+    *store=saved_store;
+    *global_tasks=saved;
+
+    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, RwLock};
+
+use chrono::DateTime;
+use chrono::format::{Item, StrftimeItems};
+use colored::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::rusty_theme::{effective_colors, theme_colors, theme_names};
+use crate::rusty_table::parse_columns;
+use crate::rusty_store::STORAGE_FORMATS;
+use crate::rusty_tasks::TASKCOM;
+
+/// Settings read from the user's config file, every field has a default
+#[derive(Debug,Clone,PartialEq,Deserialize,Serialize)]
+#[serde(default,deny_unknown_fields)]
+pub struct Config{
+    /// The task list opened at start and by a reset, the list named default
+    pub data_file: String,
+    /// Where the other named lists are kept, one file each
+    pub lists_dir: String,
+    /// markdown, json, yaml or sqlite, for data files whose extension is not .md, .json, .yaml, .yml or .db
+    pub storage_format: String,
+    /// Refuse to open a task list with lines that can not be read, otherwise it opens but is not saved until they are fixed
+    pub strict_load: bool,
+    /// How dates are shown and the long form accepted when typing a due date
+    pub date_format: String,
+    /// How dates are written in Markdown files, it needs a date, a time and an offset to read them back
+    pub storage_date_format: String,
+    /// Spaces in front of printed lists
+    pub indent: usize,
+    /// Show the banner and help when the REPL starts
+    pub show_welcome: bool,
+    pub welcome_title: String,
+    /// monday or sunday
+    pub week_start: String,
+    /// emacs or vi keys at the prompt
+    pub edit_mode: String,
+    /// Columns of the task list, from number, status, priority, due, completed, created, updated, recur and text
+    pub list_columns: Vec<String>,
+    /// How dates are shown in the task list
+    pub list_date_format: String,
+    /// Show dates in the task list as "in 2d" or "3d ago"
+    pub relative_dates: bool,
+    /// truncate or wrap task text that does not fit the terminal
+    pub long_text: String,
+    /// auto ($PAGER, or the built-in pager without it), builtin, never, or a pager command
+    pub pager: String,
+    /// What to show after a change: the changed task, the whole list or none
+    pub after_change: String,
+    /// Whether IMPORT adds to the list or replaces it: append or replace
+    pub import_mode: String,
+    /// auto, always or never, auto leaves colour out when NO_COLOR is set or output is not a terminal
+    pub color: String,
+    /// auto, always or never, never draws the checkmark as an x
+    pub unicode: String,
+    /// dark, light or one of the themes below
+    pub theme: String,
+    pub colors: Colors,
+    /// User themes by name, unset colours come from the dark theme
+    pub themes: HashMap<String,Colors>,
+    /// `name = "command text"`, typing the name runs the command text
+    pub aliases: HashMap<String,String>,
+    /// Where this config was read from and where `config set` writes to
+    #[serde(skip)]
+    pub path: Option<PathBuf>
+}
+
+impl Default for Config{
+    fn default()->Config{
+        Config{
+            data_file:"data/tasklist.md".to_string(),
+            lists_dir:"data/lists".to_string(),
+            storage_format:"markdown".to_string(),
+            strict_load:false,
+            date_format:"%Y-%m-%d %H:%M:%S %z".to_string(),
+            storage_date_format:"%Y-%m-%d %H:%M:%S%.f %:z".to_string(),
+            indent:4,
+            show_welcome:true,
+            welcome_title:"Welcome to RUSTY TASKS!".to_string(),
+            week_start:"monday".to_string(),
+            edit_mode:"emacs".to_string(),
+            list_columns:["number","status","priority","due","completed","recur","text"].map(String::from).to_vec(),
+            list_date_format:"%Y-%m-%d".to_string(),
+            relative_dates:false,
+            long_text:"truncate".to_string(),
+            pager:"auto".to_string(),
+            after_change:"task".to_string(),
+            import_mode:"append".to_string(),
+            color:"auto".to_string(),
+            unicode:"auto".to_string(),
+            theme:"dark".to_string(),
+            colors:Colors::default(),
+            themes:HashMap::new(),
+            aliases:HashMap::new(),
+            path:None
+        }
+    }
+}
+
+/// Colours of the parts of a Task, a colour name like "bright blue" or "r,g,b".
+/// Unset colours come from the theme.
+#[derive(Default,Debug,Clone,PartialEq,Deserialize,Serialize)]
+#[serde(default,deny_unknown_fields)]
+pub struct Colors{
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub done: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub todo: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub due: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub overdue: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub completed: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub recurrence: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub blocked: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub muted: Option<String>
+}
+
+impl Colors{
+    /// Every colour setting with its name, for validation
+    fn entries(&self)->[(&str,&Option<String>);10]{
+        [("label",&self.label),("text",&self.text),("done",&self.done),("todo",&self.todo),("due",&self.due),
+         ("overdue",&self.overdue),("completed",&self.completed),("recurrence",&self.recurrence),("blocked",&self.blocked),("muted",&self.muted)]
+    }
+
+    /// These colours with the ones set in `over` replacing them
+    pub fn overlay(&self,over:&Colors)->Colors{
+        let pick=|mine:&Option<String>,theirs:&Option<String>| theirs.clone().or(mine.clone());
+        Colors{
+            label:pick(&self.label,&over.label),
+            text:pick(&self.text,&over.text),
+            done:pick(&self.done,&over.done),
+            todo:pick(&self.todo,&over.todo),
+            due:pick(&self.due,&over.due),
+            overdue:pick(&self.overdue,&over.overdue),
+            completed:pick(&self.completed,&over.completed),
+            recurrence:pick(&self.recurrence,&over.recurrence),
+            blocked:pick(&self.blocked,&over.blocked),
+            muted:pick(&self.muted,&over.muted)
+        }
+    }
+}
+
+/// Reads a colour name or "r,g,b"
+pub fn parse_color(value:&str)->Result<Color,String>{
+    let parts:Vec<&str>=value.split(',').map(|p| p.trim()).collect();
+    if let [r,g,b]=parts.as_slice() {
+        return match (r.parse(),g.parse(),b.parse()) {
+            (Ok(r),Ok(g),Ok(b))=>Ok(Color::TrueColor{ r, g, b }),
+            _=>Err(format!("'{value}' is not a colour, use three numbers from 0 to 255."))
+        }
+    }
+    value.trim().parse().map_err(|_| format!("'{value}' is not a colour name."))
+}
+
+impl Config{
+    /// Checks every setting, naming the first one that is wrong
+    pub fn validate(&self)->Result<(),String>{
+        if self.data_file.trim().is_empty() {
+            return Err("data_file can not be empty.".to_string())
+        }
+        if !STORAGE_FORMATS.contains(&self.storage_format.as_str()) {
+            return Err(format!("storage_format '{}' must be one of {}.",self.storage_format,STORAGE_FORMATS.join(", ")))
+        }
+        for (name,format) in [("date_format",&self.date_format),("storage_date_format",&self.storage_date_format),("list_date_format",&self.list_date_format)]{
+            if StrftimeItems::new(format).any(|item| matches!(item,Item::Error)) {
+                return Err(format!("{name} '{format}' is not a valid chrono format."))
+            }
+        }
+        // a stored date has to read back as the same second, and a ']' would end the field it is in
+        let sample=DateTime::parse_from_rfc3339("2024-03-30T13:45:10.25+02:00").expect("sample date");
+        let stored=sample.format(&self.storage_date_format).to_string();
+        let read_back=DateTime::parse_from_str(&stored,&self.storage_date_format);
+        if stored.contains(']') || read_back.map_or(true,|date| (date-sample).num_seconds() != 0) {
+            return Err(format!("storage_date_format '{}' must write a date, a time and an offset that read back the same.",self.storage_date_format))
+        }
+        parse_columns(&self.list_columns.join(","))?;
+        if self.pager.trim().is_empty() {
+            return Err("pager can not be empty, use never to turn paging off.".to_string())
+        }
+        if !["task","list","none"].contains(&self.after_change.as_str()) {
+            return Err(format!("after_change '{}' must be task, list or none.",self.after_change))
+        }
+        if !["append","replace"].contains(&self.import_mode.as_str()) {
+            return Err(format!("import_mode '{}' must be append or replace.",self.import_mode))
+        }
+        if !["truncate","wrap"].contains(&self.long_text.as_str()) {
+            return Err(format!("long_text '{}' must be truncate or wrap.",self.long_text))
+        }
+        if self.indent > 16 {
+            return Err("indent can be at most 16.".to_string())
+        }
+        if !["monday","sunday"].contains(&self.week_start.as_str()) {
+            return Err(format!("week_start '{}' must be monday or sunday.",self.week_start))
+        }
+        if !["emacs","vi"].contains(&self.edit_mode.as_str()) {
+            return Err(format!("edit_mode '{}' must be emacs or vi.",self.edit_mode))
+        }
+        for mode in [("color",&self.color),("unicode",&self.unicode)]{
+            if !["auto","always","never"].contains(&mode.1.as_str()) {
+                return Err(format!("{} '{}' must be auto, always or never.",mode.0,mode.1))
+            }
+        }
+        if theme_colors(self,&self.theme).is_none() {
+            return Err(format!("theme '{}' is not one of {}.",self.theme,theme_names(self).join(", ")))
+        }
+        let themes=self.themes.iter().map(|(name,colors)| (format!("themes.{name}"),colors));
+        for (table,colors) in std::iter::once(("colors".to_string(),&self.colors)).chain(themes){
+            for (name,value) in colors.entries(){
+                if let Some(value)=value {
+                    parse_color(value).map_err(|error| format!("{table}.{name}: {error}"))?;
+                }
+            }
+        }
+        for (name,expansion) in &self.aliases {
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return Err(format!("alias '{name}' must be a single word."))
+            }
+            if expansion.trim().is_empty() {
+                return Err(format!("alias '{name}' has no command."))
+            }
+            if TASKCOM::into_iter_client().any(|command| command.to_string().eq_ignore_ascii_case(name)) {
+                return Err(format!("alias '{name}' would hide the {} command, pick another name.",name.to_uppercase()))
+            }
+        }
+        Ok(())
+    }
+
+    /// Alias names are matched like command names, without regard to case
+    fn lowercase_aliases(&mut self)->Result<(),String>{
+        let mut aliases=HashMap::new();
+        for (name,expansion) in std::mem::take(&mut self.aliases){
+            let lowered=name.to_lowercase();
+            if aliases.contains_key(&lowered) {
+                return Err(format!("alias '{lowered}' is defined more than once."))
+            }
+            aliases.insert(lowered,expansion);
+        }
+        self.aliases=aliases;
+        Ok(())
+    }
+
+    /// Reads one setting by its key, like `indent` or `colors.label`, colours left to the theme included
+    pub fn get(&self,key:&str)->Result<String,String>{
+        let mut shown=self.clone();
+        shown.colors=effective_colors(self);
+        let table=toml::Value::try_from(&shown).map_err(|error| error.to_string())?;
+        let value=key.split('.').try_fold(&table,|value,part| value.get(part))
+            .ok_or_else(|| format!("There is no setting '{key}'."))?;
+        Ok(match value {
+            toml::Value::String(text)=>text.clone(),
+            other=>other.to_string()
+        })
+    }
+
+    /// Changes one setting, keeping the old config when the new value is not valid
+    pub fn set(&mut self,key:&str,value:&str)->Result<(),String>{
+        let mut table=toml::Value::try_from(&*self).map_err(|error| error.to_string())?;
+        let (parents,name)=match key.rsplit_once('.') {
+            Some((parents,name))=>(parents.split('.').collect::<Vec<&str>>(),name),
+            None=>(Vec::new(),key)
+        };
+        let mut parent=&mut table;
+        for part in parents{
+            parent=parent.get_mut(part).ok_or_else(|| format!("There is no setting '{key}'."))?;
+        }
+        let toml::Value::Table(parent)=parent else {
+            return Err(format!("There is no setting '{key}'."))
+        };
+        // numbers and booleans are read as TOML, anything else is text
+        let value=toml::from_str::<toml::Table>(&format!("value = {value}"))
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+            .unwrap_or_else(|| toml::Value::String(value.to_string()));
+        parent.insert(name.to_string(),value);
+
+        let mut config:Config=table.try_into().map_err(|error:toml::de::Error| error.message().to_string())?;
+        config.lowercase_aliases()?;
+        config.validate()?;
+        config.path=self.path.clone();
+        *self=config;
+        Ok(())
+    }
+
+    /// Writes the config to its file as TOML
+    pub fn save(&self)->Result<PathBuf,String>{
+        let path=self.path.clone().or_else(config_filepath).ok_or("There is no place to save the config.")?;
+        if let Some(parent)=path.parent() {
+            std::fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+        }
+        let data=toml::to_string_pretty(self).map_err(|error| error.to_string())?;
+        std::fs::write(&path,data).map_err(|error| error.to_string())?;
+        Ok(path)
+    }
+}
+
+/// $XDG_CONFIG_HOME/rusty-tasks/config.toml, or ~/.config/rusty-tasks/config.toml
+pub fn config_filepath()->Option<PathBuf>{
+    let base=match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty()=>PathBuf::from(dir),
+        _=>PathBuf::from(std::env::var_os("HOME")?).join(".config")
+    };
+    Some(base.join("rusty-tasks").join("config.toml"))
+}
+
+/// Reads and validates a config from TOML text
+pub fn parse_config(data:&str)->Result<Config,String>{
+    let mut config:Config=toml::from_str(data).map_err(|error| error.message().to_string())?;
+    config.lowercase_aliases()?;
+    config.validate()?;
+    Ok(config)
+}
+
+/// Loads the config from `--config` or the default location.
+/// A missing default file gives the defaults, a missing `--config` file or a bad setting is an error.
+pub fn load_config(override_path:Option<PathBuf>)->Result<Config,String>{
+    let explicit=override_path.is_some();
+    let Some(path)=override_path.or_else(config_filepath) else {
+        return Ok(Config::default())
+    };
+    let mut config=match std::fs::read_to_string(&path) {
+        Ok(data)=>parse_config(&data).map_err(|error| format!("{}: {error}",path.display()))?,
+        Err(_) if !explicit=>Config::default(),
+        Err(error)=>return Err(format!("Could not read config file {}: {error}",path.display()))
+    };
+    config.path=Some(path);
+    Ok(config)
+}
+
+static SETTINGS:LazyLock<RwLock<Arc<Config>>>=LazyLock::new(|| RwLock::new(Arc::new(Config::default())));
+
+/// The config in use, the defaults until install_config is called.
+/// This is a snapshot, so no lock is held while it is used and code that reads settings can call code that does too.
+pub fn settings()->Arc<Config>{
+    SETTINGS.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+/// Makes a config the one in use
+pub fn install_config(config:Config){
+    *SETTINGS.write().unwrap_or_else(|poisoned| poisoned.into_inner())=Arc::new(config);
+}
@@ -0,0 +1,191 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc};
+use itertools::Itertools;
+
+use crate::rusty_tasks::*;
+use crate::rusty_recurrence::Recurrence;
+
+/// Every column a CSV export has, in order; an import needs at least text and may leave out or reorder the rest
+pub const CSV_COLUMNS:[&str;12]=["id","status","priority","text","due","completed","recur","depends_on","notes","created","updated","uid"];
+
+/// Quotes a field when it holds a comma, quote or line break, doubling its quotes
+fn quote_field(field:&str)->String{
+    if field.contains([',','"','\r','\n']) {
+        format!("\"{}\"",field.replace('"',"\"\""))
+    }else{
+        field.to_string()
+    }
+}
+
+/// Due dates typed as a day are local midnight and go out as a plain day, everything else as UTC
+fn format_date(date:Option<DateTime<Utc>>,day_if_midnight:bool)->String{
+    match date {
+        Some(date) if day_if_midnight && date.with_timezone(&Local).time() == NaiveTime::default()=>date.with_timezone(&Local).format("%Y-%m-%d").to_string(),
+        Some(date)=>date.to_rfc3339_opts(SecondsFormat::Secs,true),
+        None=>String::new()
+    }
+}
+
+/// Reads ISO 8601 days and date-times, times without an offset are local
+fn parse_date(value:&str)->Result<DateTime<Utc>,String>{
+    let value=value.trim();
+    let local=|naive:NaiveDateTime| match Local.from_local_datetime(&naive).earliest() {
+        Some(date)=>date.with_timezone(&Utc),
+        None=>naive.and_utc()
+    };
+    if let Ok(date)=DateTime::parse_from_rfc3339(value) {
+        return Ok(date.with_timezone(&Utc))
+    }
+    if let Ok(day)=NaiveDate::parse_from_str(value,"%Y-%m-%d") {
+        return Ok(local(day.and_time(NaiveTime::default())))
+    }
+    ["%Y-%m-%dT%H:%M:%S","%Y-%m-%d %H:%M:%S","%Y-%m-%dT%H:%M","%Y-%m-%d %H:%M"].iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value,format).ok())
+        .map(local)
+        .ok_or_else(|| format!("'{value}' is not an ISO 8601 date"))
+}
+
+/// Convert tasklist to CSV with a header row, notes are joined with line breaks
+pub fn convert_tltocsv(tl:&TaskList)->String{
+    let mut result=CSV_COLUMNS.join(",")+"\r\n";
+    for task in tl.tasks.iter(){
+        let fields=[
+            task.id.to_string(),
+            if task.completed { "done" } else { "open" }.to_string(),
+            task.priority.map(String::from).unwrap_or_default(),
+            task.data.clone(),
+            format_date(task.due_date,true),
+            format_date(task.completed_date,false),
+            task.recurrence.as_ref().map(|r| r.to_string()).unwrap_or_default(),
+            task.depends_on.iter().join(" "),
+            task.notes.join("\n"),
+            format_date(task.created_at,false),
+            format_date(task.updated_at,false),
+            task.uid.clone().unwrap_or_default()
+        ];
+        result+=&(fields.iter().map(|f| quote_field(f)).join(",")+"\r\n");
+    }
+    result
+}
+
+/// Splits CSV into records following RFC 4180, keeping the line each record starts on.
+/// Quoted fields may hold commas, doubled quotes and line breaks.
+pub fn parse_csv_records(data:&str)->Result<Vec<(usize,Vec<String>)>,String>{
+    let mut records:Vec<(usize,Vec<String>)>=Vec::new();
+    let mut record:Vec<String>=Vec::new();
+    let mut field=String::new();
+    let mut quoted=false;
+    let mut line=1;
+    let mut start=1;
+    let mut chars=data.chars().peekable();
+    while let Some(c)=chars.next(){
+        match (quoted,c) {
+            (true,'"') if chars.peek() == Some(&'"')=>{
+                chars.next();
+                field.push('"');
+            },
+            (true,'"')=>quoted=false,
+            (true,c)=>{
+                if c == '\n' { line+=1; }
+                field.push(c);
+            },
+            (false,'"') if field.is_empty()=>quoted=true,
+            (false,',')=>record.push(std::mem::take(&mut field)),
+            (false,'\r') if chars.peek() == Some(&'\n')=>{},
+            (false,'\n')=>{
+                record.push(std::mem::take(&mut field));
+                // blank lines are not records
+                if record.len() > 1 || !record[0].is_empty() {
+                    records.push((start,std::mem::take(&mut record)));
+                }
+                record.clear();
+                line+=1;
+                start=line;
+            },
+            (false,c)=>field.push(c)
+        }
+    }
+    if quoted {
+        return Err(format!("Line {start}: a quoted field is never closed."))
+    }
+    record.push(field);
+    if record.len() > 1 || !record[0].is_empty() {
+        records.push((start,record));
+    }
+    Ok(records)
+}
+
+/// Builds a Task from one record, naming the field that could not be read
+fn parse_row(header:&[usize],record:&[String])->Result<Task,String>{
+    if record.len() != header.len() {
+        return Err(format!("has {} fields but the header has {}",record.len(),header.len()))
+    }
+    let mut task=Task::new(false,String::new());
+    for (column,value) in header.iter().zip(record){
+        let name=CSV_COLUMNS[*column];
+        let error=|problem:String| format!("{name}: {problem}");
+        let optional_date=|value:&str| if value.trim().is_empty() { Ok(None) } else { parse_date(value).map(Some).map_err(error) };
+        match name {
+            "id"=>task.id=if value.trim().is_empty() { 0 } else { value.trim().parse().map_err(|_| error(format!("'{value}' is not a number")))? },
+            "status"=>task.completed=match value.trim().to_lowercase().as_str() {
+                "done" | "x" | "true" | "yes" | "completed"=>true,
+                "open" | "" | "false" | "no"=>false,
+                other=>return Err(error(format!("'{other}' is not done or open")))
+            },
+            "priority"=>task.priority=match value.trim() {
+                ""=>None,
+                other=>Some(parse_priority(other).ok_or_else(|| error(format!("'{other}' is not a letter")))?)
+            },
+            // a task is one line in the Markdown file, so text over several lines is joined
+            "text"=>task.data=single_line(value),
+            "due"=>task.due_date=optional_date(value)?,
+            "completed"=>task.completed_date=optional_date(value)?,
+            "recur"=>task.recurrence=match value.trim() {
+                ""=>None,
+                rule=>Some(rule.parse::<Recurrence>().map_err(error)?)
+            },
+            "depends_on"=>task.depends_on=value.split([' ',';']).filter(|id| !id.is_empty())
+                .map(|id| id.parse::<usize>().map_err(|_| error(format!("'{id}' is not a task id"))))
+                .try_collect()?,
+            "notes"=>task.notes=value.lines().flat_map(|line| line.split('\r')).map(|l| l.to_string()).collect(),
+            "created"=>task.created_at=optional_date(value)?,
+            "updated"=>task.updated_at=optional_date(value)?,
+            "uid"=>task.uid=parse_uid(value),
+            _=>{}
+        }
+    }
+    if task.data.is_empty() {
+        return Err("text is empty".to_string())
+    }
+    if task.completed && task.completed_date.is_none() {
+        task.completed_date=Some(Utc::now());
+    }
+    Ok(task)
+}
+
+/// Convert CSV to tasklist. Columns are found by their header name in any order.
+/// Rows that can not be read are left out and described, with their line, in the returned problems.
+pub fn convert_csvtotl(data:&str)->Result<(TaskList,Vec<String>),String>{
+    let mut records=parse_csv_records(data.trim_start_matches('\u{feff}'))?.into_iter();
+    let Some((_,names))=records.next() else { return Err("The file is empty.".to_string()) };
+    let header:Vec<usize>=names.iter()
+        .map(|name|{
+            let name=name.trim().to_lowercase();
+            CSV_COLUMNS.iter().position(|c| *c == name)
+                .ok_or_else(|| format!("Line 1: '{name}' is not a column, use {}.",CSV_COLUMNS.join(", ")))
+        })
+        .try_collect()?;
+    if !header.iter().any(|c| CSV_COLUMNS[*c] == "text") {
+        return Err("Line 1: the header needs a text column.".to_string())
+    }
+
+    let mut tl=TaskList{ tasks:Vec::new() };
+    let mut problems:Vec<String>=Vec::new();
+    for (line,record) in records{
+        match parse_row(&header,&record) {
+            Ok(task)=>tl.tasks.push(task),
+            Err(problem)=>problems.push(format!("Line {line}: {problem}."))
+        }
+    }
+    tl.ensure_ids();
+    Ok((tl,problems))
+}
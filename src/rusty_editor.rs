@@ -0,0 +1,79 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::rusty_tasks::*;
+
+/// Returns the editor command from $VISUAL or $EDITOR, falling back to a platform default
+pub fn get_editor()->String{
+    let default_editor = if cfg!(windows) { "notepad" } else { "vi" };
+    ["VISUAL","EDITOR"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or(default_editor.to_string())
+}
+
+/// Builds a temp file path that won't collide with other sessions
+fn temp_filepath(suffix:&str)->PathBuf{
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+    std::env::temp_dir().join(format!("rusty-tasks-{}-{}{}",std::process::id(),nanos,suffix))
+}
+
+/// Writes text to a temp file, opens it in the user's editor and returns the saved text
+pub fn edit_text(initial:&str,suffix:&str)->Result<String,String>{
+    let filepath=temp_filepath(suffix);
+    fs::write(&filepath, initial).map_err(|error| format!("Could not create temp file: {error}"))?;
+
+    // editors like "code --wait" come with their own arguments
+    let editor=get_editor();
+    let mut parts=editor.split_whitespace();
+    let program=parts.next().unwrap_or_default().to_string();
+    let status=Command::new(&program)
+        .args(parts)
+        .arg(&filepath)
+        .status();
+
+    let result=match status {
+        Ok(exit) if exit.success()=>fs::read_to_string(&filepath).map_err(|error| format!("Could not read temp file: {error}")),
+        Ok(exit)=>Err(format!("Editor '{program}' exited with {exit}, changes were discarded.")),
+        Err(error)=>Err(format!("Could not start editor '{program}': {error}"))
+    };
+    let _ = fs::remove_file(&filepath);
+    result
+}
+
+/// Asks a question on the terminal and returns the trimmed, lowercased answer
+pub fn prompt(question:&str)->String{
+    let mut input = String::new();
+    print!("{question} ");
+    io::stdout().flush().unwrap_or_default();
+    // EOF or a read error count as an empty answer so callers fall back to their safe default
+    io::stdin().read_line(&mut input).unwrap_or_default();
+    input.trim().to_lowercase()
+}
+
+/// Describes what changed between two versions of a list, matching Tasks by id
+pub fn summarize_changes(before:&[Task],after:&[Task])->Vec<String>{
+    let mut summary=Vec::new();
+    for task in after{
+        match before.iter().find(|t| t.id == task.id){
+            None=>summary.push(format!("+ added: {}",task.data)),
+            Some(old) if old != task=>summary.push(format!("~ changed: {}",task.data)),
+            Some(_)=>{}
+        }
+    }
+    for task in before{
+        if !after.iter().any(|t| t.id == task.id) {
+            summary.push(format!("- removed: {}",task.data));
+        }
+    }
+    let before_ids:Vec<usize>=before.iter().map(|t| t.id).filter(|id| after.iter().any(|t| t.id == *id)).collect();
+    let after_ids:Vec<usize>=after.iter().map(|t| t.id).filter(|id| before.iter().any(|t| t.id == *id)).collect();
+    if before_ids != after_ids {
+        summary.push("* tasks were reordered".to_string());
+    }
+    summary
+}
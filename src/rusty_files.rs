@@ -1,135 +1,161 @@
-use std::fs::{create_dir_all, OpenOptions};
-use std::io::{Error, ErrorKind, Read, Write};
-use std::path::Path;
-use std::fs::File;
-
-use crate::rusty_tasks::*;
-
-/// Save tasklist struct to file
-pub fn save_tltofile(filepath:String,tasklist:TaskList)->Result<String,Error>{
-    let string_tasklist=convert_tltostring(tasklist);
-
-    // println!("Requested path: {}",filepath);// ? debug
-
-    // Create intermediate directories if they don't exist
-    if let Some(parent) = std::path::Path::new(&filepath).parent() {
-        if let Err(directory_missing_error) = create_dir_all(parent) {
-            eprintln!("Error creating directories: {}", directory_missing_error);
-            return Err(directory_missing_error);
-        }
-    };
-
-    // Check if the file exists before handling
-    let file_exists = std::path::Path::new(&filepath).exists();
-
-    if file_exists{     
-        if let Err(file_exist_error) = handle_existing_file(&filepath,&string_tasklist){
-                eprintln!("Invalid handling of existing file:{file_exist_error}")
-        }
-    }else{
-        //println!("not exists");// ? debug
-        if let Err(new_file_error) = handle_new_file(&filepath, &string_tasklist){
-                eprintln!("Invalid handling of new file:{new_file_error}")
-        }
-    }
-    Ok("File saved successfully.".to_string())
-}
-
-/// Non-existing file save
-pub fn handle_new_file(filepath: &str, data: &str)->Result<(),Error>{
-    // Check if we have write permissions for the folder
-    let parent_directory = Path::new(filepath).parent().ok_or_else(|| {
-        eprintln!("Error getting parent directory for file: {}", filepath);
-        Error::new(ErrorKind::Other, "Invalid parent directory")
-    })?;
-
-    let parent_notreadonly=!parent_directory.metadata()?.permissions().readonly();
-
-    if parent_notreadonly { // Folder has write permissions, create file        
-        let mut file = File::create(filepath)?;
-        file.write_all(data.as_bytes())?;
-    } else {
-        eprintln!("Error: No write permissions for the new file's parent directory.");
-        return Err(Error::new(ErrorKind::PermissionDenied, "No write permissions for parent directory"));
-    }
-
-    Ok(())
-}
-
-/// Existing file save
-pub fn handle_existing_file(filepath: &str, data: &str)->Result<(),Error>{
-    let data_directory = std::env::current_dir()?.join("data");
-    let data_directory_canon = data_directory.canonicalize()?.to_string_lossy().to_string();
-    let filepath_canon = Path::new(filepath).canonicalize()?.to_string_lossy().to_string();
-
-    if !filepath_canon.contains(&data_directory_canon) {
-        eprintln!(
-            "Error: Path does not contain the 'data' directory: {} datadir: {}",
-            filepath_canon, data_directory_canon
-        );
-        return Err(Error::new(ErrorKind::Other, "Invalid path"));
-    }
-
-    let file_exists = Path::new(filepath).exists();
-    let not_readonly = !Path::new(filepath).metadata()?.permissions().readonly();
-
-    match (file_exists, not_readonly) {
-        (true, true) => {
-            // File exists, have permissions, overwrite it
-            let mut file = OpenOptions::new().write(true).truncate(true).create(true).open(filepath)?;
-            file.write_all(data.as_bytes())?;
-        }
-        (true, false) => {
-            // File exists, no permissions, error
-            eprintln!("Error: No write permissions for the existing file.");
-            return Err(Error::new(ErrorKind::PermissionDenied, "No write permissions"));
-        }
-        (false, _) => {
-            // File not exists
-        }
-    }
-
-    Ok(())
-}
-
-/// Load tasklist struct from file
-pub fn load_tlfromfile(path:String)->TaskList{
-    let mut data = String::new();
-    let mut file = File::open(&path).ok();
-    let mut file_opened=false;
-    match file{
-        Some(ref mut _f)=>{file_opened=true;}
-        None=>{
-            #[cfg(debug_assertions)]{//prevent this from running in release
-                eprintln!("Error loading: File not found at path '{}'", &path);
-            }
-        }
-    };
-    if file_opened{
-        file.unwrap().read_to_string(&mut data).unwrap_or_default();
-    }
-    convert_stringtotl(data)
-}
-
-pub fn set_defaultfilepath(global_datafilepath:&mut String, filepath:String)->Result<(),Error>{
-    // validate filepath shapes
-    let validate_path = Path::new(&filepath).parent();
-
-    match validate_path {
-        Some(_)=>{},
-        None=>{return Err(Error::new(ErrorKind::Other, "Invalid filepath provided for set_defaultfilepath."))}
-    }
-    
-    // set the reference string to filepath string after validation
-    // This does not copy the String data; instead, it transfers ownership of filepath to the original String that global_datafilepath points to.
-    *global_datafilepath=filepath;
-
-    Ok(())
-}
-
-pub fn reset_defaultfilepath(global_datafilepath:&mut String){
-    let default_filepath = "data/tasklist.md";
-
-    // discard the error because there shouldn't be one as long as devs don't mess  up the default_filepath
-    set_defaultfilepath(global_datafilepath, default_filepath.to_string()).ok();
+use std::fs::{create_dir_all, OpenOptions};
+use std::io::{Error, ErrorKind, Write};
+use std::path::Path;
+use std::fs::File;
+
+use crate::rusty_tasks::*;
+use crate::rusty_config::settings;
+use crate::rusty_store::{store_for, TaskStore};
+use crate::rusty_workspace::Workspace;
+
+/// Save tasklist struct to file
+pub fn save_tltofile(filepath:String,tasklist:TaskList)->Result<String,Error>{
+    store_for(&filepath)?.save(&tasklist)?;
+    Ok("File saved successfully.".to_string())
+}
+
+/// Writes the text of a data file, creating its directories
+pub fn write_textfile(filepath:&str,string_tasklist:&str)->Result<(),Error>{
+    // println!("Requested path: {}",filepath);// ? debug
+
+    // Create intermediate directories if they don't exist
+    if let Some(parent) = Path::new(filepath).parent() {
+        if let Err(directory_missing_error) = create_dir_all(parent) {
+            eprintln!("Error creating directories: {}", directory_missing_error);
+            return Err(directory_missing_error);
+        }
+    };
+
+    // Check if the file exists before handling
+    let file_exists = Path::new(filepath).exists();
+
+    // a failed save goes back to the command, which reports it
+    if file_exists{
+        handle_existing_file(filepath,string_tasklist)
+    }else{
+        handle_new_file(filepath, string_tasklist)
+    }
+}
+
+/// Writes text that is not a tasklist, like a backup, with the same checks as a tasklist save
+pub fn save_textfile(filepath:&str,data:&str)->Result<(),Error>{
+    if let Some(parent) = Path::new(filepath).parent() {
+        create_dir_all(parent)?;
+    }
+    if Path::new(filepath).exists() {
+        handle_existing_file(filepath,data)
+    }else{
+        handle_new_file(filepath,data)
+    }
+}
+
+/// Writes an export where the user asked, replacing the file that is there.
+/// Unlike task list saves it is not kept to the data directory.
+pub fn export_textfile(filepath:&str,data:&str)->Result<(),Error>{
+    if let Some(parent) = Path::new(filepath).parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        create_dir_all(parent)?;
+    }
+    std::fs::write(filepath,data)
+}
+
+/// Non-existing file save
+pub fn handle_new_file(filepath: &str, data: &str)->Result<(),Error>{
+    // Check if we have write permissions for the folder
+    let parent_directory = Path::new(filepath).parent().ok_or_else(|| {
+        eprintln!("Error getting parent directory for file: {}", filepath);
+        Error::other("Invalid parent directory")
+    })?;
+
+    let parent_notreadonly=!parent_directory.metadata()?.permissions().readonly();
+
+    if parent_notreadonly { // Folder has write permissions, create file        
+        let mut file = File::create(filepath)?;
+        file.write_all(data.as_bytes())?;
+    } else {
+        eprintln!("Error: No write permissions for the new file's parent directory.");
+        return Err(Error::new(ErrorKind::PermissionDenied, "No write permissions for parent directory"));
+    }
+
+    Ok(())
+}
+
+/// Refuses to replace an existing file that is not in ./data or lists_dir and is not the data_file
+pub fn check_overwrite(filepath: &str)->Result<(),Error>{
+    if !Workspace::from_settings().may_overwrite(filepath) {
+        return Err(Error::other(format!("{filepath} is not in ./data or lists_dir and is not the data_file, files elsewhere are not overwritten")));
+    }
+    Ok(())
+}
+
+/// Existing file save
+pub fn handle_existing_file(filepath: &str, data: &str)->Result<(),Error>{
+    check_overwrite(filepath)?;
+
+    let file_exists = Path::new(filepath).exists();
+    let not_readonly = !Path::new(filepath).metadata()?.permissions().readonly();
+
+    match (file_exists, not_readonly) {
+        (true, true) => {
+            // File exists, have permissions, overwrite it
+            let mut file = OpenOptions::new().write(true).truncate(true).create(true).open(filepath)?;
+            file.write_all(data.as_bytes())?;
+        }
+        (true, false) => {
+            // File exists, no permissions, error
+            eprintln!("Error: No write permissions for the existing file.");
+            return Err(Error::new(ErrorKind::PermissionDenied, "No write permissions"));
+        }
+        (false, _) => {
+            // File not exists
+        }
+    }
+
+    Ok(())
+}
+
+/// Load tasklist struct from file, a missing file gives an empty TaskList
+/// and a file that does not parse is reported
+pub fn load_tlfromfile(path:String)->TaskList{
+    match try_load_tlfromfile(&path) {
+        Ok(tasklist)=>tasklist,
+        Err(error)=>{
+            eprintln!("Error loading: {error}");
+            TaskList{ tasks:Vec::new() }
+        }
+    }
+}
+
+/// Load tasklist struct from the store its file uses
+pub fn try_load_tlfromfile(path:&str)->Result<TaskList,Error>{
+    open_store(path).map(|(_,tasklist)| tasklist)
+}
+
+/// Opens the store for a data file and loads its Tasks
+pub fn open_store(path:&str)->Result<(Box<dyn TaskStore>,TaskList),Error>{
+    let mut store=store_for(path)?;
+    let tasklist=store.load()?;
+    Ok((store,tasklist))
+}
+
+pub fn set_defaultfilepath(global_datafilepath:&mut String, filepath:String)->Result<(),Error>{
+    // validate filepath shapes
+    let validate_path = Path::new(&filepath).parent();
+
+    match validate_path {
+        Some(_)=>{},
+        None=>{return Err(Error::other("Invalid filepath provided for set_defaultfilepath."))}
+    }
+    
+    // set the reference string to filepath string after validation
+    // This does not copy the String data; instead, it transfers ownership of filepath to the original String that global_datafilepath points to.
+    *global_datafilepath=filepath;
+
+    Ok(())
+}
+
+pub fn reset_defaultfilepath(global_datafilepath:&mut String){
+    let default_filepath = settings().data_file.clone();
+
+    // discard the error because the config was validated at startup
+    set_defaultfilepath(global_datafilepath, default_filepath).ok();
 }
\ No newline at end of file
@@ -0,0 +1,235 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::rusty_tasks::*;
+
+const ICAL_DATE_FORMAT:&str="%Y%m%d";
+const ICAL_DATETIME_FORMAT:&str="%Y%m%dT%H%M%S";
+/// Lines longer than this many bytes are folded
+const ICAL_LINE_LIMIT:usize=75;
+
+/// The UID of a Task, the one it was imported with or else one that is the same on every export
+/// as long as the Task is in the list
+pub fn task_uid(task:&Task)->String{
+    if let Some(uid)=&task.uid {
+        return uid.clone()
+    }
+    let created=task.created_at.map(|c| c.timestamp()).unwrap_or(0);
+    format!("{}-{created}@rusty-tasks",task.id)
+}
+
+/// Escapes text values, newlines become \n
+fn escape_text(text:&str)->String{
+    text.replace('\\',"\\\\").replace(';',"\\;").replace(',',"\\,").replace('\n',"\\n")
+}
+
+/// Undoes escape_text
+fn unescape_text(text:&str)->String{
+    let mut result=String::new();
+    let mut chars=text.chars();
+    while let Some(c)=chars.next(){
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N')=>result.push('\n'),
+            Some(escaped)=>result.push(escaped),
+            None=>{}
+        }
+    }
+    result
+}
+
+/// Folds a content line into lines of at most 75 bytes, continuation lines start with a space
+fn fold_line(line:&str)->String{
+    let mut result=String::new();
+    let mut used=0;
+    for c in line.chars(){
+        let limit=if result.is_empty() { ICAL_LINE_LIMIT } else { ICAL_LINE_LIMIT-1 };
+        if used+c.len_utf8() > limit {
+            result+="\r\n ";
+            used=0;
+        }
+        used+=c.len_utf8();
+        result.push(c);
+    }
+    result+"\r\n"
+}
+
+fn format_utc(date:DateTime<Utc>)->String{
+    date.format(ICAL_DATETIME_FORMAT).to_string()+"Z"
+}
+
+/// Due dates typed as a day are local midnight and go out as a DATE, any other time as UTC
+fn format_due(date:DateTime<Utc>)->String{
+    let local=date.with_timezone(&Local);
+    if local.time() == NaiveTime::default() {
+        format!("DUE;VALUE=DATE:{}",local.format(ICAL_DATE_FORMAT))
+    }else{
+        format!("DUE:{}",format_utc(date))
+    }
+}
+
+/// Writes one Task as a VTODO
+fn format_vtodo(task:&Task,stamp:DateTime<Utc>)->String{
+    let mut lines:Vec<String>=vec![
+        "BEGIN:VTODO".to_string(),
+        format!("UID:{}",task_uid(task)),
+        format!("DTSTAMP:{}",format_utc(stamp)),
+        format!("SUMMARY:{}",escape_text(&task.data))
+    ];
+    if !task.notes.is_empty() {
+        lines.push(format!("DESCRIPTION:{}",escape_text(&task.notes.join("\n"))));
+    }
+    if let Some(date)=task.due_date {
+        lines.push(format_due(date));
+    }
+    if let Some(level)=task.priority.and_then(|priority| (priority as u32).checked_sub('A' as u32)) {
+        // A is 1, the most important, and everything after I is 9
+        lines.push(format!("PRIORITY:{}",(level+1).min(9)));
+    }
+    lines.push(format!("STATUS:{}",if task.completed { "COMPLETED" } else { "NEEDS-ACTION" }));
+    if let (true,Some(date))=(task.completed,task.completed_date) {
+        lines.push(format!("COMPLETED:{}",format_utc(date)));
+    }
+    if let Some(date)=task.created_at {
+        lines.push(format!("CREATED:{}",format_utc(date)));
+    }
+    if let Some(date)=task.updated_at {
+        lines.push(format!("LAST-MODIFIED:{}",format_utc(date)));
+    }
+    lines.push("END:VTODO".to_string());
+    lines.iter().map(|line| fold_line(line)).collect()
+}
+
+/// Convert tasklist to an iCalendar file with one VTODO per Task
+pub fn convert_tltoical(tl:&TaskList)->String{
+    let stamp=Utc::now();
+    let mut result=String::new();
+    for line in ["BEGIN:VCALENDAR","VERSION:2.0","PRODID:-//Rusty Tasks//EN"]{
+        result+=&fold_line(line);
+    }
+    for task in tl.tasks.iter(){
+        result+=&format_vtodo(task,stamp);
+    }
+    result+&fold_line("END:VCALENDAR")
+}
+
+/// One unfolded content line, `NAME;PARAM=value:value`
+struct ContentLine{
+    number: usize,
+    name: String,
+    params: Vec<(String,String)>,
+    value: String
+}
+
+impl ContentLine{
+    fn param(&self,key:&str)->Option<&str>{
+        self.params.iter().find(|(k,_)| k == key).map(|(_,v)| v.as_str())
+    }
+}
+
+/// Joins folded lines back together, keeping the number of the line each one started on
+fn unfold_lines(data:&str)->Vec<(usize,String)>{
+    let mut lines:Vec<(usize,String)>=Vec::new();
+    for (n,line) in data.lines().enumerate(){
+        match (line.strip_prefix([' ','\t']),lines.last_mut()) {
+            (Some(rest),Some((_,last)))=>*last+=rest,
+            _ if line.trim().is_empty()=>{},
+            _=>lines.push((n+1,line.to_string()))
+        }
+    }
+    lines
+}
+
+/// Splits a content line at the first colon outside a quoted parameter value
+fn parse_content_line(number:usize,line:&str)->Option<ContentLine>{
+    let mut quoted=false;
+    let colon=line.char_indices().find(|(_,c)|{
+        if *c == '"' { quoted = !quoted; }
+        *c == ':' && !quoted
+    })?.0;
+    let mut parts=line[..colon].split(';');
+    let name=parts.next()?.trim().to_uppercase();
+    let params=parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k,v)| (k.trim().to_uppercase(),v.trim_matches('"').to_string()))
+        .collect();
+    Some(ContentLine{ number, name, params, value:line[colon+1..].to_string() })
+}
+
+/// Reads DATE, UTC, TZID and floating date-times. A floating time, or a TZID this build
+/// does not know, is read as local time.
+fn parse_ical_date(line:&ContentLine)->Result<DateTime<Utc>,String>{
+    let value=line.value.trim();
+    let error=|| format!("Line {}: could not read {} '{value}'.",line.number,line.name);
+    let local=|naive:NaiveDateTime| match Local.from_local_datetime(&naive).earliest() {
+        Some(date)=>date.with_timezone(&Utc),
+        None=>naive.and_utc()
+    };
+    if line.param("VALUE") == Some("DATE") || value.len() == 8 {
+        let day=NaiveDate::parse_from_str(value,ICAL_DATE_FORMAT).map_err(|_| error())?;
+        return Ok(local(day.and_time(NaiveTime::default())))
+    }
+    if let Some(utc)=value.strip_suffix('Z') {
+        return NaiveDateTime::parse_from_str(utc,ICAL_DATETIME_FORMAT).map(|naive| naive.and_utc()).map_err(|_| error())
+    }
+    let naive=NaiveDateTime::parse_from_str(value,ICAL_DATETIME_FORMAT).map_err(|_| error())?;
+    match line.param("TZID").and_then(|tzid| tzid.parse::<Tz>().ok()) {
+        Some(zone)=>Ok(zone.from_local_datetime(&naive).earliest().map(|date| date.with_timezone(&Utc)).unwrap_or(naive.and_utc())),
+        None=>Ok(local(naive))
+    }
+}
+
+/// Convert an iCalendar file to tasklist, reading every VTODO and ignoring events and alarms
+pub fn convert_icaltotl(data:&str)->Result<TaskList,String>{
+    let mut tl=TaskList{ tasks:Vec::new() };
+    let mut task:Option<Task>=None;
+    // components nested in a VTODO, like VALARM, have properties of their own
+    let mut nested=0;
+    for (number,text) in unfold_lines(data){
+        let Some(line)=parse_content_line(number,&text) else {
+            return Err(format!("Line {number}: '{text}' is not an iCalendar line."))
+        };
+        let value=line.value.trim().to_uppercase();
+        match (line.name.as_str(),task.as_mut()) {
+            ("BEGIN",None) if value == "VTODO"=>task=Some(Task::new(false,String::new())),
+            ("BEGIN",Some(_))=>nested+=1,
+            ("END",Some(_)) if nested > 0=>nested-=1,
+            ("END",Some(_)) if value == "VTODO"=>{
+                let mut finished=task.take().unwrap_or_default();
+                if finished.completed && finished.completed_date.is_none() {
+                    finished.completed_date=finished.updated_at.or(Some(Utc::now()));
+                }
+                tl.tasks.push(finished);
+            },
+            (_,Some(_)) if nested > 0=>{},
+            // a task is one line in the Markdown file, so a summary over several lines is joined
+            ("SUMMARY",Some(current))=>current.data=single_line(&unescape_text(&line.value)),
+            // a ']' would end the field the UID is kept in
+            ("UID",Some(current))=>current.uid=parse_uid(&line.value),
+            ("DESCRIPTION",Some(current))=>current.notes=unescape_text(&line.value).lines().map(|l| l.to_string()).collect(),
+            ("DUE",Some(current))=>current.due_date=Some(parse_ical_date(&line)?),
+            ("COMPLETED",Some(current))=>{
+                current.completed=true;
+                current.completed_date=Some(parse_ical_date(&line)?);
+            },
+            ("STATUS",Some(current))=>current.completed=value == "COMPLETED",
+            ("PRIORITY",Some(current))=>{
+                current.priority=match value.parse::<u8>() {
+                    Ok(level @ 1..=9)=>Some((b'A'+level-1) as char),
+                    _=>None
+                };
+            },
+            ("CREATED",Some(current))=>current.created_at=Some(parse_ical_date(&line)?),
+            ("LAST-MODIFIED",Some(current))=>current.updated_at=Some(parse_ical_date(&line)?),
+            _=>{}
+        }
+    }
+    if task.is_some() {
+        return Err("The file ends inside a VTODO, it may be cut short.".to_string())
+    }
+    tl.ensure_ids();
+    Ok(tl)
+}
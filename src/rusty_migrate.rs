@@ -0,0 +1,80 @@
+use regex::Regex;
+
+use crate::rusty_tasks::*;
+
+/// One step of the migration chain, the text of a file in one layout turned into the next
+type Migration=fn(&str)->String;
+
+/// MIGRATIONS[n] upgrades a file from format version n+1 to n+2, add one for every FORMAT_VERSION bump
+const MIGRATIONS:[Migration;1]=[upgrade_version_1];
+
+/// Version 1 files have a bare `# TaskList:` header and task text written as it was
+fn upgrade_version_1(data:&str)->String{
+    escape_task_texts(&stamp_version_header(data))
+}
+
+/// Escapes task text that starts with `[` or `\`. Only a line whose fields start with an Id has fields,
+/// lines from before task ids are all text, so a leading `[Priority: A]` there stays part of it.
+fn escape_task_texts(data:&str)->String{
+    let re_full=Regex::new(TASK_LINE_PATTERN).unwrap();
+    let re_field=Regex::new(TASK_FIELD_PATTERN).unwrap();
+    data.split_inclusive('\n')
+        .map(|line|{
+            if line.starts_with(NOTE_INDENT) {
+                return line.to_string()
+            }
+            let body=line.trim_end_matches(['\r','\n']);
+            let Some(rest)=re_full.captures(body).and_then(|captures| captures.get(4)) else {
+                return line.to_string()
+            };
+            let mut text=rest.as_str();
+            if text.starts_with("[Id: ") {
+                while let Some(field)=re_field.find(text){
+                    text=&text[field.end()..];
+                }
+            }
+            let start=rest.end()-text.len();
+            format!("{}{}{}",&line[..start],escape_task_text(text),&line[rest.end()..])
+        })
+        .collect()
+}
+
+/// Puts the version marker in the `# TaskList:` header
+fn stamp_version_header(data:&str)->String{
+    let mut stamped=false;
+    data.split_inclusive('\n')
+        .map(|line|{
+            if !stamped && line.contains("# TaskList:") {
+                stamped=true;
+                line.replacen("# TaskList:","# TaskList: [Version: 2]",1)
+            }else{
+                line.to_string()
+            }
+        })
+        .collect()
+}
+
+/// The format version in the header of a Markdown task list, files without one are version 1
+pub fn markdown_version(data:&str)->Result<u32,String>{
+    let re_version=Regex::new(r"\[Version: (.*?)\]").unwrap();
+    let Some(header)=data.lines().find(|line| line.contains("# TaskList:")) else { return Ok(1) };
+    match re_version.captures(header) {
+        Some(captures)=>captures[1].trim().parse::<u32>()
+            .ok()
+            .filter(|version| *version > 0)
+            .ok_or_else(|| format!("'{}' is not a format version",&captures[1])),
+        None=>Ok(1)
+    }
+}
+
+/// Brings a Markdown task list up to FORMAT_VERSION, returning the version it had.
+/// Files from a newer version are refused rather than read and saved without what this one does not know.
+pub fn migrate_markdown(data:&str)->Result<(String,u32),String>{
+    let version=markdown_version(data)?;
+    if version > FORMAT_VERSION {
+        return Err(format!("it was written by a newer rusty-tasks (format {version}, this one reads up to {FORMAT_VERSION}), please upgrade"))
+    }
+    let upgraded=MIGRATIONS[(version-1) as usize..(FORMAT_VERSION-1) as usize].iter()
+        .fold(data.to_string(),|text,migration| migration(&text));
+    Ok((upgraded,version))
+}
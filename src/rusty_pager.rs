@@ -0,0 +1,86 @@
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use colored::Colorize;
+
+use crate::rusty_config::settings;
+use crate::rusty_theme::palette;
+
+/// Lines of the terminal, only when both input and output are a terminal someone can page with
+fn terminal_height()->Option<usize>{
+    if !io::stdout().is_terminal() || !io::stdin().is_terminal() {
+        return None
+    }
+    crossterm::terminal::size().ok().map(|(_,rows)| rows as usize)
+}
+
+/// The pager program from the pager setting: auto uses $PAGER, builtin or an unset $PAGER gives None
+pub fn pager_command()->Option<String>{
+    let pager=settings().pager.clone();
+    match pager.as_str() {
+        "auto"=>std::env::var("PAGER").ok().filter(|p| !p.trim().is_empty()),
+        "builtin" | "never"=>None,
+        command=>Some(command.to_string())
+    }
+}
+
+/// Splits text into pages that leave one line for the prompt
+pub fn split_pages(text:&str,height:usize)->Vec<String>{
+    let lines:Vec<&str>=text.lines().collect();
+    lines.chunks(height.saturating_sub(1).max(1)).map(|page| page.join("\n")).collect()
+}
+
+/// Prints text, through a pager when it is taller than the terminal
+pub fn print_paged(text:&str){
+    let height=match terminal_height() {
+        Some(height) if settings().pager != "never" && text.lines().count() >= height=>height,
+        _=>{
+            println!("{text}");
+            return
+        }
+    };
+    match pager_command() {
+        Some(command)=>{
+            if let Err(error)=run_pager(&command,text) {
+                eprintln!("Could not run pager '{command}': {error}");
+                println!("{text}");
+            }
+        },
+        None=>builtin_pager(text,height)
+    }
+}
+
+/// Pipes text into an external pager, letting less show colours
+fn run_pager(command:&str,text:&str)->io::Result<()>{
+    let mut parts=command.split_whitespace();
+    let program=parts.next().ok_or_else(|| io::Error::other("the pager setting is empty"))?;
+    let mut pager=Command::new(program);
+    pager.args(parts).stdin(Stdio::piped());
+    if std::env::var_os("LESS").is_none() {
+        pager.env("LESS","FRX");
+    }
+    let mut child=pager.spawn()?;
+    if let Some(mut stdin)=child.stdin.take() {
+        // the pager closing early (q) is not an error
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait()?;
+    Ok(())
+}
+
+/// Shows one page at a time, Enter shows the next and q stops
+fn builtin_pager(text:&str,height:usize){
+    let pages=split_pages(text,height);
+    for (n,page) in pages.iter().enumerate(){
+        println!("{page}");
+        if n+1 == pages.len() {
+            break;
+        }
+        print!("{}",format!("-- page {} of {}, Enter for more, q to stop --",n+1,pages.len()).color(palette().muted));
+        let _ = io::stdout().flush();
+        let mut answer=String::new();
+        if io::stdin().read_line(&mut answer).unwrap_or(0) == 0 || answer.trim().eq_ignore_ascii_case("q") {
+            break;
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use itertools::Itertools;
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{CompletionType, Config, Context, EditMode, Editor, Helper};
+
+use crate::rusty_tasks::*;
+use crate::rusty_config::settings;
+
+/// Where the REPL keeps its command history between sessions
+pub const HISTORY_FILEPATH:&str="data/.history";
+
+/// Commands whose arguments start with a task number
+const INDEX_COMMANDS:[&str;9]=["remove","complete","depend","undepend","recur","note","show","edit","edit-in-editor"];
+
+/// Commands whose argument is a file path
+const FILE_COMMANDS:[&str;2]=["load","saveas"];
+
+/// Tab completion for command names, task numbers, tags and file paths
+pub struct TaskHelper{
+    files: FilenameCompleter,
+    task_count: usize,
+    tags: Vec<String>
+}
+
+impl TaskHelper{
+    pub fn new()->TaskHelper{
+        TaskHelper{
+            files:FilenameCompleter::new(),
+            task_count:0,
+            tags:Vec::new()
+        }
+    }
+
+    /// Refreshes what can be completed from the current TaskList
+    pub fn update(&mut self,tl:&TaskList){
+        self.task_count=tl.tasks.len();
+        self.tags=tl.tasks.iter().flat_map(|t| t.tags()).unique().sorted().collect();
+    }
+
+    /// Lists the completions for the word at the end of `before`, returning where that word starts
+    pub fn candidates(&self,before:&str)->(usize,Vec<String>){
+        let command_names=TASKCOM::into_iter_client().map(|c| c.to_string().to_lowercase());
+        let Some((command,_))=before.split_once(char::is_whitespace) else {
+            let lowered=before.to_lowercase();
+            return (0,command_names.filter(|c| c.starts_with(&lowered)).collect())
+        };
+        let command=command.to_lowercase();
+        let start=before.rfind([' ',',']).map(|i| i+1).unwrap_or(0);
+        let word=&before[start..];
+
+        let candidates=if command == "help" {
+            command_names.filter(|c| c.starts_with(word)).collect()
+        }else if word.starts_with(['#','+','@']) {
+            self.tags.iter().filter(|t| t.starts_with(word)).cloned().collect()
+        }else if INDEX_COMMANDS.contains(&command.as_str()) && word.chars().all(|c| c.is_ascii_digit()) {
+            (1..=self.task_count).map(|n| n.to_string()).filter(|n| n.starts_with(word)).collect()
+        }else{
+            Vec::new()
+        };
+        (start,candidates)
+    }
+}
+
+impl Default for TaskHelper{
+    fn default()->TaskHelper{
+        TaskHelper::new()
+    }
+}
+
+impl Completer for TaskHelper{
+    type Candidate = Pair;
+
+    fn complete(&self,line:&str,pos:usize,ctx:&Context<'_>)->rustyline::Result<(usize,Vec<Pair>)>{
+        let before=&line[..pos];
+        let command=before.split_whitespace().next().unwrap_or("").to_lowercase();
+        if before.contains(char::is_whitespace) && FILE_COMMANDS.contains(&command.as_str()) {
+            return self.files.complete(line,pos,ctx)
+        }
+        let (start,candidates)=self.candidates(before);
+        let pairs=candidates.into_iter()
+            .map(|c| Pair{ display:c.clone(), replacement:c })
+            .collect();
+        Ok((start,pairs))
+    }
+}
+
+impl Hinter for TaskHelper{
+    type Hint = String;
+}
+
+impl Highlighter for TaskHelper{}
+
+impl Validator for TaskHelper{}
+
+impl Helper for TaskHelper{}
+
+/// Builds the line editor, emacs keys by default or vi keys with the edit_mode setting
+pub fn create_editor()->rustyline::Result<Editor<TaskHelper,DefaultHistory>>{
+    let edit_mode=match settings().edit_mode.as_str() {
+        "vi"=>EditMode::Vi,
+        _=>EditMode::Emacs
+    };
+    let config=Config::builder()
+        .edit_mode(edit_mode)
+        .completion_type(CompletionType::List)
+        .auto_add_history(true)
+        .max_history_size(1000)?
+        .build();
+    let mut editor=Editor::with_config(config)?;
+    editor.set_helper(Some(TaskHelper::new()));
+    // a missing history file just means this is the first session
+    let _ = editor.load_history(HISTORY_FILEPATH);
+    Ok(editor)
+}
+
+/// Reads one line with editing and completion, Ctrl-D exits and Ctrl-C clears the line
+pub fn read_line(editor:&mut Editor<TaskHelper,DefaultHistory>,tl:&TaskList,prompt:&str)->String{
+    if let Some(helper)=editor.helper_mut() {
+        helper.update(tl);
+    }
+    match editor.readline(prompt) {
+        Ok(line)=>{
+            // saved every line because EXIT ends the process without unwinding
+            if !line.trim().is_empty() {
+                save_history(editor);
+            }
+            line
+        },
+        Err(ReadlineError::Interrupted)=>String::new(),
+        Err(ReadlineError::Eof)=>"exit".to_string(),
+        Err(error)=>{
+            eprintln!("Could not read line: {error}");
+            "exit".to_string()
+        }
+    }
+}
+
+/// Writes the history file, creating the data folder when needed
+pub fn save_history(editor:&mut Editor<TaskHelper,DefaultHistory>){
+    if let Some(parent)=std::path::Path::new(HISTORY_FILEPATH).parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Err(error)=editor.save_history(HISTORY_FILEPATH) {
+        eprintln!("Could not save history: {error}");
+    }
+}
@@ -0,0 +1,142 @@
+use std::{fmt, str::FromStr};
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate, TimeZone, Utc, Weekday};
+use itertools::Itertools;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Represents how often a Task comes back after it is completed.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Recurrence{
+    Daily,
+    Weekly(Vec<Weekday>),
+    Monthly(u32),
+    AfterCompletion(u32)
+}
+
+/// Parses rules like `daily`, `weekly mon,fri`, `monthly 15` and `every 3d`
+impl FromStr for Recurrence{
+    type Err = String;
+    fn from_str(input: &str) -> Result<Recurrence, Self::Err> {
+        let lowered=input.trim().to_lowercase();
+        let mut parts=lowered.splitn(2, char::is_whitespace);
+        let kind=parts.next().unwrap_or("");
+        let rest=parts.next().unwrap_or("").trim();
+        match kind {
+            "daily"=>Ok(Recurrence::Daily),
+            "weekly"=>{
+                let days:Result<Vec<Weekday>,_>=rest
+                    .split(|c:char| c==',' || c.is_whitespace())
+                    .filter(|d| !d.is_empty())
+                    .map(|d| d.parse::<Weekday>().map_err(|_| format!("Unknown weekday '{d}'.")))
+                    .collect();
+                let mut days=days?;
+                if days.is_empty() {
+                    return Err("Weekly rules need at least one weekday, like 'weekly mon,fri'.".to_string())
+                }
+                days.sort_by_key(|d| d.num_days_from_monday());
+                days.dedup();
+                Ok(Recurrence::Weekly(days))
+            },
+            "monthly"=>match rest.parse::<u32>(){
+                Ok(day) if (1..=31).contains(&day)=>Ok(Recurrence::Monthly(day)),
+                _=>Err("Monthly rules need a day between 1 and 31, like 'monthly 15'.".to_string())
+            },
+            "every"=>match rest.trim_end_matches(['d','D']).trim().parse::<u32>(){
+                Ok(days) if days > 0=>Ok(Recurrence::AfterCompletion(days)),
+                _=>Err("Every rules need a number of days, like 'every 3d'.".to_string())
+            },
+            _=>Err(format!("Unknown recurrence rule '{input}'."))
+        }
+    }
+}
+
+/// Writes the same form that FromStr reads so rules round trip through the data file
+impl fmt::Display for Recurrence{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily=>write!(f,"daily"),
+            Recurrence::Weekly(days)=>{
+                let day_string=days.iter().map(|d| d.to_string().to_lowercase()).join(",");
+                write!(f,"weekly {day_string}")
+            },
+            Recurrence::Monthly(day)=>write!(f,"monthly {day}"),
+            Recurrence::AfterCompletion(days)=>write!(f,"every {days}d")
+        }
+    }
+}
+
+/// Stored as the rule text, like `weekly mon,fri`
+impl Serialize for Recurrence{
+    fn serialize<S:Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Recurrence{
+    fn deserialize<D:Deserializer<'de>>(deserializer: D) -> Result<Recurrence, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Recurrence{
+    /// Works out the due date of the next instance.
+    /// Calendar rules step forward from the previous due date (or the completion when there
+    /// was none) until they land after the completion, so late completions don't create overdue copies.
+    pub fn next_due(&self,due_date:Option<DateTime<Utc>>,completed_date:DateTime<Utc>)->DateTime<Utc>{
+        let completed_local=completed_date.with_timezone(&Local);
+        let base=due_date.unwrap_or(completed_date).with_timezone(&Local);
+        let time=base.time();
+        let mut date=base.date_naive();
+
+        if let Recurrence::AfterCompletion(days)=self {
+            date=completed_local.date_naive()+Days::new(*days as u64);
+            return local_to_utc(date,time)
+        }
+
+        loop{
+            date=match self {
+                Recurrence::Daily=>date+Days::new(1),
+                Recurrence::Weekly(days)=>next_weekday(date,days),
+                Recurrence::Monthly(day)=>next_month_day(date,*day),
+                Recurrence::AfterCompletion(_)=>unreachable!()
+            };
+            let candidate=local_to_utc(date,time);
+            if candidate > completed_date {
+                return candidate
+            }
+        }
+    }
+}
+
+/// First date after `date` that falls on one of `days`
+fn next_weekday(date:NaiveDate,days:&[Weekday])->NaiveDate{
+    let mut next=date+Days::new(1);
+    while !days.contains(&next.weekday()) {
+        next=next+Days::new(1);
+    }
+    next
+}
+
+/// The next date after `date` on the given day of a month, clamped to the length of that month
+fn next_month_day(date:NaiveDate,day:u32)->NaiveDate{
+    let this_month=clamp_day(date.with_day(1).unwrap_or(date),day);
+    if this_month > date {
+        return this_month
+    }
+    clamp_day(date.with_day(1).unwrap_or(date)+Months::new(1),day)
+}
+
+/// The given day in the month of `first`, or the last day when the month is shorter
+fn clamp_day(first:NaiveDate,day:u32)->NaiveDate{
+    (1..=day).rev()
+        .find_map(|d| first.with_day(d))
+        .unwrap_or(first)
+}
+
+/// Turns a local calendar date and time into UTC, falling back to UTC when the local time doesn't exist
+fn local_to_utc(date:NaiveDate,time:chrono::NaiveTime)->DateTime<Utc>{
+    let naive=date.and_time(time);
+    match Local.from_local_datetime(&naive).earliest(){
+        Some(value)=>value.with_timezone(&Utc),
+        None=>naive.and_utc()
+    }
+}
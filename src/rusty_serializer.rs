@@ -0,0 +1,97 @@
+use crate::rusty_tasks::*;
+use crate::rusty_store::storage_format_for;
+use crate::rusty_migrate::migrate_markdown;
+
+/// Turns a TaskList into the text of a data file and back
+pub trait TaskSerializer{
+    /// The name from STORAGE_FORMATS
+    fn name(&self)->&'static str;
+    fn serialize(&self,tl:&TaskList)->Result<String,String>;
+    fn deserialize(&self,data:&str)->Result<TaskList,String>;
+    /// Lines that deserialize reads only in part or not at all, with their number and the reason
+    fn problems(&self,_data:&str)->Vec<(usize,String)>{
+        Vec::new()
+    }
+    /// The data rewritten in the current layout and the version it had, None when it is current
+    fn upgrade(&self,_data:&str)->Result<Option<(String,u32)>,String>{
+        Ok(None)
+    }
+}
+
+/// The `# TaskList:` Markdown, lines it can not read are skipped and listed by problems
+pub struct MarkdownSerializer;
+
+impl TaskSerializer for MarkdownSerializer{
+    fn name(&self)->&'static str{
+        "markdown"
+    }
+
+    fn serialize(&self,tl:&TaskList)->Result<String,String>{
+        Ok(convert_tltostring(tl.clone()))
+    }
+
+    fn deserialize(&self,data:&str)->Result<TaskList,String>{
+        let (data,_)=migrate_markdown(data)?;
+        Ok(convert_stringtotl(data))
+    }
+
+    fn problems(&self,data:&str)->Vec<(usize,String)>{
+        find_parse_problems(data)
+    }
+
+    fn upgrade(&self,data:&str)->Result<Option<(String,u32)>,String>{
+        let (upgraded,version)=migrate_markdown(data)?;
+        Ok((version < FORMAT_VERSION).then_some((upgraded,version)))
+    }
+}
+
+pub struct JsonSerializer;
+
+impl TaskSerializer for JsonSerializer{
+    fn name(&self)->&'static str{
+        "json"
+    }
+
+    fn serialize(&self,tl:&TaskList)->Result<String,String>{
+        serde_json::to_string_pretty(tl).map(|data| data+"\n").map_err(|error| error.to_string())
+    }
+
+    fn deserialize(&self,data:&str)->Result<TaskList,String>{
+        let mut tl:TaskList=serde_json::from_str(data).map_err(|error| error.to_string())?;
+        tl.ensure_ids();
+        Ok(tl)
+    }
+}
+
+pub struct YamlSerializer;
+
+impl TaskSerializer for YamlSerializer{
+    fn name(&self)->&'static str{
+        "yaml"
+    }
+
+    fn serialize(&self,tl:&TaskList)->Result<String,String>{
+        serde_yaml::to_string(tl).map_err(|error| error.to_string())
+    }
+
+    fn deserialize(&self,data:&str)->Result<TaskList,String>{
+        let mut tl:TaskList=serde_yaml::from_str(data).map_err(|error| error.to_string())?;
+        tl.ensure_ids();
+        Ok(tl)
+    }
+}
+
+/// The serializer for a text format from STORAGE_FORMATS
+pub fn serializer_named(name:&str)->Option<Box<dyn TaskSerializer>>{
+    match name {
+        "markdown"=>Some(Box::new(MarkdownSerializer)),
+        "json"=>Some(Box::new(JsonSerializer)),
+        "yaml"=>Some(Box::new(YamlSerializer)),
+        _=>None
+    }
+}
+
+/// The serializer for a text data file, Markdown unless its storage format is JSON or YAML
+pub fn serializer_for(filepath:&str)->Box<dyn TaskSerializer>{
+    serializer_named(&storage_format_for(filepath)).unwrap_or(Box::new(MarkdownSerializer))
+}
@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+use std::fs::{create_dir_all, File};
+use std::io::{Error, ErrorKind, Read};
+use std::path::Path;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OpenFlags};
+
+use crate::rusty_tasks::*;
+use crate::rusty_config::settings;
+use crate::rusty_files::{check_overwrite, save_textfile, write_textfile};
+use crate::rusty_serializer::serializer_for;
+
+/// Names of the storage formats, as the storage_format setting takes them
+pub const STORAGE_FORMATS:[&str;4]=["markdown","json","yaml","sqlite"];
+
+/// Where a TaskList is kept between runs. Commands save through this, so a new backend
+/// only needs an implementation and a case in store_for.
+pub trait TaskStore{
+    /// The file the Tasks are kept in, or "memory"
+    fn location(&self)->String;
+    fn load(&mut self)->Result<TaskList,Error>;
+    /// Saves the whole list
+    fn save(&mut self,tl:&TaskList)->Result<(),Error>;
+    /// Saves after only the Tasks at indexes were changed or added.
+    /// Stores that can write single Tasks override this, the others save everything.
+    fn save_changed(&mut self,tl:&TaskList,_indexes:&[usize])->Result<(),Error>{
+        self.save(tl)
+    }
+}
+
+/// The storage format of a data file: by extension, otherwise the storage_format setting
+pub fn storage_format_for(filepath:&str)->String{
+    let extension=Path::new(filepath).extension().map(|e| e.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("md" | "markdown")=>"markdown".to_string(),
+        Some("json")=>"json".to_string(),
+        Some("yaml" | "yml")=>"yaml".to_string(),
+        Some("db" | "sqlite" | "sqlite3")=>"sqlite".to_string(),
+        _=>settings().storage_format.clone()
+    }
+}
+
+/// Opens the store for a data file
+pub fn store_for(filepath:&str)->Result<Box<dyn TaskStore>,Error>{
+    match storage_format_for(filepath).as_str() {
+        "sqlite"=>Ok(Box::new(SqliteStore::open(filepath)?)),
+        _=>Ok(Box::new(FileStore{ path:filepath.to_string(), strict:settings().strict_load }))
+    }
+}
+
+/// A text file in Markdown, JSON or YAML, rewritten on every save
+pub struct FileStore{
+    pub path: String,
+    /// Refuse to load a file with lines that can not be read, from the strict_load setting
+    pub strict: bool
+}
+
+impl TaskStore for FileStore{
+    fn location(&self)->String{
+        self.path.clone()
+    }
+
+    fn load(&mut self)->Result<TaskList,Error>{
+        let mut data = String::new();
+        let mut modified:DateTime<Utc>=Utc::now();
+        match File::open(&self.path) {
+            Ok(mut file)=>{
+                if let Ok(time)=file.metadata().and_then(|m| m.modified()) {
+                    modified=time.into();
+                }
+                file.read_to_string(&mut data)?;
+            },
+            Err(_)=>{
+                #[cfg(debug_assertions)]{//prevent this from running in release
+                    eprintln!("Error loading: File not found at path '{}'", &self.path);
+                }
+            }
+        }
+        // a new file has nothing to parse in any format
+        if data.trim().is_empty() {
+            return Ok(TaskList{ tasks:Vec::new() })
+        }
+        let serializer=serializer_for(&self.path);
+        let invalid=|error:String| Error::new(ErrorKind::InvalidData,format!("{} is not a {} task list: {error}",self.path,serializer.name()));
+        // an older layout is upgraded in memory only, the file changes at the first save
+        if let Some((upgraded,_))=serializer.upgrade(&data).map_err(invalid)? {
+            data=upgraded;
+        }
+        let problems=serializer.problems(&data);
+        if !problems.is_empty() {
+            let listing=format_parse_problems(&problems);
+            if self.strict {
+                return Err(invalid(format!("some lines could not be read, fix them or turn off strict_load to open it:\r\n{listing}")))
+            }
+            eprintln!("Some lines of {} could not be read, it is not saved until they are fixed:\r\n{listing}",self.path);
+        }
+        let mut tasklist=serializer.deserialize(&data).map_err(invalid)?;
+        // older files have no created/updated times, the file itself is the best evidence we have
+        tasklist.backfill_timestamps(modified);
+        Ok(tasklist)
+    }
+
+    fn save(&mut self,tl:&TaskList)->Result<(),Error>{
+        let serializer=serializer_for(&self.path);
+        if let Ok(existing)=std::fs::read_to_string(&self.path) {
+            // a file in an older layout is checked as load read it, upgraded
+            let upgrade=serializer.upgrade(&existing).ok().flatten();
+            let current=upgrade.as_ref().map_or(existing.as_str(),|(upgraded,_)| upgraded.as_str());
+            // overwriting lines that were not understood would lose them
+            if !serializer.problems(current).is_empty() {
+                return Err(Error::new(ErrorKind::InvalidData,"saving would drop lines that could not be read. \
+                    Run 'check' to see them, fix the file and load it again, or use saveas to save to another file."))
+            }
+            if let Some((_,version))=upgrade {
+                self.keep_original(&existing,version)?;
+            }
+        }
+        let data=serializer.serialize(tl).map_err(Error::other)?;
+        write_textfile(&self.path,&data)
+    }
+}
+
+impl FileStore{
+    /// Keeps a file in an older layout next to it before the first save replaces it with the current one
+    fn keep_original(&self,original:&str,version:u32)->Result<(),Error>{
+        let backup=format!("{}.v{version}.bak",self.path);
+        // a backup from an earlier attempt is the true original
+        if !Path::new(&backup).exists() {
+            save_textfile(&backup,original).map_err(|error| Error::other(format!("could not keep the format {version} file as {backup}: {error}")))?;
+            println!("Upgraded {} from format {version} to {FORMAT_VERSION}, the old file is kept as {backup}.",self.path);
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the Tasks in memory only, for tests and for trying commands out
+#[derive(Default)]
+pub struct MemoryStore{
+    pub tasks: Vec<Task>,
+    /// How many times the list was saved
+    pub saves: usize
+}
+
+impl TaskStore for MemoryStore{
+    fn location(&self)->String{
+        "memory".to_string()
+    }
+
+    fn load(&mut self)->Result<TaskList,Error>{
+        Ok(TaskList{ tasks:self.tasks.clone() })
+    }
+
+    fn save(&mut self,tl:&TaskList)->Result<(),Error>{
+        self.tasks=tl.tasks.clone();
+        self.saves+=1;
+        Ok(())
+    }
+}
+
+/// Keeps every save of the store it wraps in memory until commit, so batch mode writes
+/// the file once at the end and leaves it as it was when a line fails
+pub struct BatchStore<'a>{
+    inner: &'a mut dyn TaskStore,
+    pending: Option<TaskList>
+}
+
+impl<'a> BatchStore<'a>{
+    pub fn new(inner:&'a mut dyn TaskStore)->BatchStore<'a>{
+        BatchStore{ inner, pending:None }
+    }
+
+    /// Writes the last list saved, if anything was saved
+    pub fn commit(self)->Result<(),Error>{
+        match self.pending {
+            Some(tl)=>self.inner.save(&tl),
+            None=>Ok(())
+        }
+    }
+}
+
+impl TaskStore for BatchStore<'_>{
+    fn location(&self)->String{
+        self.inner.location()
+    }
+
+    fn load(&mut self)->Result<TaskList,Error>{
+        match &self.pending {
+            Some(tl)=>Ok(tl.clone()),
+            None=>self.inner.load()
+        }
+    }
+
+    fn save(&mut self,tl:&TaskList)->Result<(),Error>{
+        self.pending=Some(tl.clone());
+        Ok(())
+    }
+}
+
+/// An SQLite database that writes only the Tasks that changed, all in one transaction
+pub struct SqliteStore{
+    path: String,
+    connection: Connection,
+    /// The stored Tasks by id with their positions, as of the last load or save, so a save reads nothing
+    stored: Option<HashMap<usize,(usize,Task)>>,
+    /// Why saves are refused: an existing database check_overwrite refused is opened read-only,
+    /// as FileStore never replaces such files
+    refused: Option<String>
+}
+
+const SQLITE_SCHEMA:&str="
+    CREATE TABLE IF NOT EXISTS tasks(
+        id INTEGER PRIMARY KEY,
+        position INTEGER NOT NULL,
+        completed INTEGER NOT NULL,
+        data TEXT NOT NULL,
+        due_date TEXT,
+        completed_date TEXT,
+        recurrence TEXT,
+        priority TEXT,
+        created_at TEXT,
+        updated_at TEXT,
+        uid TEXT
+    );
+    CREATE TABLE IF NOT EXISTS task_dependencies(
+        task_id INTEGER NOT NULL,
+        depends_on INTEGER NOT NULL,
+        PRIMARY KEY(task_id,depends_on)
+    );
+    CREATE TABLE IF NOT EXISTS task_notes(
+        task_id INTEGER NOT NULL,
+        line INTEGER NOT NULL,
+        text TEXT NOT NULL,
+        PRIMARY KEY(task_id,line)
+    );
+    CREATE TABLE IF NOT EXISTS task_tags(
+        task_id INTEGER NOT NULL,
+        tag TEXT NOT NULL,
+        PRIMARY KEY(task_id,tag)
+    );
+    CREATE INDEX IF NOT EXISTS tasks_due_date ON tasks(due_date);
+    CREATE INDEX IF NOT EXISTS tasks_completed ON tasks(completed,completed_date);
+    CREATE INDEX IF NOT EXISTS task_tags_tag ON task_tags(tag);
+";
+
+fn sqlite_error(error:rusqlite::Error)->Error{
+    Error::other(format!("SQLite: {error}"))
+}
+
+/// Dates are kept as fixed width UTC text so they sort, and the indexes work, as text
+fn format_date(date:Option<DateTime<Utc>>)->Option<String>{
+    date.map(|d| d.to_rfc3339_opts(SecondsFormat::Nanos,true))
+}
+
+fn parse_date(value:Option<String>)->Option<DateTime<Utc>>{
+    value.and_then(|v| DateTime::parse_from_rfc3339(&v).ok()).map(|d| d.with_timezone(&Utc))
+}
+
+impl SqliteStore{
+    /// Opens or creates the database with its tables and indexes
+    pub fn open(filepath:&str)->Result<SqliteStore,Error>{
+        if let (true,Err(error))=(Path::new(filepath).exists(),check_overwrite(filepath)) {
+            let connection=Connection::open_with_flags(filepath,OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(sqlite_error)?;
+            return Ok(SqliteStore{ path:filepath.to_string(), connection, stored:None, refused:Some(error.to_string()) })
+        }
+        if let Some(parent)=Path::new(filepath).parent().filter(|p| !p.as_os_str().is_empty()) {
+            create_dir_all(parent)?;
+        }
+        let connection=Connection::open(filepath).map_err(sqlite_error)?;
+        connection.execute_batch(SQLITE_SCHEMA).map_err(sqlite_error)?;
+        // databases from before calendar UIDs get the column
+        if connection.prepare("SELECT uid FROM tasks LIMIT 0").is_err() {
+            connection.execute("ALTER TABLE tasks ADD COLUMN uid TEXT",[]).map_err(sqlite_error)?;
+        }
+        Ok(SqliteStore{ path:filepath.to_string(), connection, stored:None, refused:None })
+    }
+
+    /// Every stored Task by id, with its position in the list
+    fn read_tasks(connection:&Connection)->rusqlite::Result<HashMap<usize,(usize,Task)>>{
+        let mut tasks:HashMap<usize,(usize,Task)>=HashMap::new();
+        // a read-only database from before calendar UIDs was not given the column
+        let uid=if connection.prepare("SELECT uid FROM tasks LIMIT 0").is_ok() { "uid" } else { "NULL" };
+        let mut statement=connection.prepare(&format!(
+            "SELECT id,position,completed,data,due_date,completed_date,recurrence,priority,created_at,updated_at,{uid} FROM tasks"))?;
+        let rows=statement.query_map([],|row|{
+            let task=Task{
+                id:row.get::<_,i64>(0)? as usize,
+                completed:row.get(2)?,
+                data:row.get(3)?,
+                due_date:parse_date(row.get(4)?),
+                completed_date:parse_date(row.get(5)?),
+                recurrence:row.get::<_,Option<String>>(6)?.and_then(|rule| rule.parse().ok()),
+                priority:row.get::<_,Option<String>>(7)?.and_then(|p| parse_priority(&p)),
+                created_at:parse_date(row.get(8)?),
+                updated_at:parse_date(row.get(9)?),
+                uid:row.get(10)?,
+                ..Default::default()
+            };
+            Ok((row.get::<_,i64>(1)? as usize,task))
+        })?;
+        for row in rows{
+            let (position,task)=row?;
+            tasks.insert(task.id,(position,task));
+        }
+        let mut statement=connection.prepare("SELECT task_id,depends_on FROM task_dependencies ORDER BY rowid")?;
+        for row in statement.query_map([],|row| Ok((row.get::<_,i64>(0)? as usize,row.get::<_,i64>(1)? as usize)))?{
+            let (id,prerequisite)=row?;
+            if let Some((_,task))=tasks.get_mut(&id) {
+                task.depends_on.push(prerequisite);
+            }
+        }
+        let mut statement=connection.prepare("SELECT task_id,text FROM task_notes ORDER BY task_id,line")?;
+        for row in statement.query_map([],|row| Ok((row.get::<_,i64>(0)? as usize,row.get::<_,String>(1)?)))?{
+            let (id,note)=row?;
+            if let Some((_,task))=tasks.get_mut(&id) {
+                task.notes.push(note);
+            }
+        }
+        Ok(tasks)
+    }
+
+    /// Inserts or replaces one Task with its dependencies, notes and tags
+    fn write_task(connection:&Connection,position:usize,task:&Task)->rusqlite::Result<()>{
+        connection.execute(
+            "INSERT OR REPLACE INTO tasks(id,position,completed,data,due_date,completed_date,recurrence,priority,created_at,updated_at,uid)
+             VALUES(?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11)",
+            params![task.id as i64,position as i64,task.completed,task.data,format_date(task.due_date),format_date(task.completed_date),
+                task.recurrence.as_ref().map(|r| r.to_string()),task.priority.map(String::from),
+                format_date(task.created_at),format_date(task.updated_at),task.uid])?;
+        Self::delete_details(connection,task.id)?;
+        for prerequisite in task.depends_on.iter(){
+            connection.execute("INSERT OR IGNORE INTO task_dependencies(task_id,depends_on) VALUES(?1,?2)",params![task.id as i64,*prerequisite as i64])?;
+        }
+        for (line,note) in task.notes.iter().enumerate(){
+            connection.execute("INSERT INTO task_notes(task_id,line,text) VALUES(?1,?2,?3)",params![task.id as i64,line as i64,note])?;
+        }
+        for tag in task.tags(){
+            connection.execute("INSERT OR IGNORE INTO task_tags(task_id,tag) VALUES(?1,?2)",params![task.id as i64,tag.to_lowercase()])?;
+        }
+        Ok(())
+    }
+
+    fn delete_details(connection:&Connection,id:usize)->rusqlite::Result<()>{
+        let id=id as i64;
+        connection.execute("DELETE FROM task_dependencies WHERE task_id=?1",[id])?;
+        connection.execute("DELETE FROM task_notes WHERE task_id=?1",[id])?;
+        connection.execute("DELETE FROM task_tags WHERE task_id=?1",[id])?;
+        Ok(())
+    }
+}
+
+impl TaskStore for SqliteStore{
+    fn location(&self)->String{
+        self.path.clone()
+    }
+
+    fn load(&mut self)->Result<TaskList,Error>{
+        let stored=Self::read_tasks(&self.connection).map_err(sqlite_error)?;
+        let mut tasks:Vec<(usize,Task)>=stored.values().cloned().collect();
+        tasks.sort_by_key(|(position,_)| *position);
+        self.stored=Some(stored);
+        Ok(TaskList{ tasks:tasks.into_iter().map(|(_,task)| task).collect() })
+    }
+
+    fn save(&mut self,tl:&TaskList)->Result<(),Error>{
+        if let Some(reason)=&self.refused {
+            return Err(Error::other(reason.clone()))
+        }
+        // the ids are the primary key, so a list from before ids gets them first
+        let mut tl=tl.clone();
+        tl.ensure_ids();
+        // the table is read only when this store has not loaded it
+        let stored=match self.stored.take() {
+            Some(stored)=>stored,
+            None=>Self::read_tasks(&self.connection).map_err(sqlite_error)?
+        };
+        let transaction=self.connection.transaction().map_err(sqlite_error)?;
+        for (position,task) in tl.tasks.iter().enumerate(){
+            match stored.get(&task.id) {
+                Some((was,before)) if before == task=>{
+                    // a Task that only moved, like after a removal above it, gets just its new position
+                    if *was != position {
+                        transaction.execute("UPDATE tasks SET position=?1 WHERE id=?2",[position as i64,task.id as i64]).map_err(sqlite_error)?;
+                    }
+                },
+                _=>Self::write_task(&transaction,position,task).map_err(sqlite_error)?
+            }
+        }
+        for id in stored.keys().filter(|id| tl.index_of_id(**id).is_none()){
+            transaction.execute("DELETE FROM tasks WHERE id=?1",[*id as i64]).map_err(sqlite_error)?;
+            Self::delete_details(&transaction,*id).map_err(sqlite_error)?;
+        }
+        // nothing is written unless every change is, a failed save reads the table again next time
+        transaction.commit().map_err(sqlite_error)?;
+        self.stored=Some(tl.tasks.into_iter().enumerate().map(|(position,task)| (task.id,(position,task))).collect());
+        Ok(())
+    }
+
+    /// Writes just the changed rows, without reading the table first
+    fn save_changed(&mut self,tl:&TaskList,indexes:&[usize])->Result<(),Error>{
+        if let Some(reason)=&self.refused {
+            return Err(Error::other(reason.clone()))
+        }
+        let missing_ids=indexes.iter().any(|i| tl.tasks.get(*i).is_none_or(|task| task.id == 0));
+        if missing_ids {
+            return self.save(tl)
+        }
+        let transaction=self.connection.transaction().map_err(sqlite_error)?;
+        for index in indexes{
+            Self::write_task(&transaction,*index,&tl.tasks[*index]).map_err(sqlite_error)?;
+        }
+        if let Err(error)=transaction.commit() {
+            self.stored=None;
+            return Err(sqlite_error(error))
+        }
+        if let Some(stored)=self.stored.as_mut() {
+            for index in indexes{
+                let task=&tl.tasks[*index];
+                stored.insert(task.id,(*index,task.clone()));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,323 @@
+use std::io::IsTerminal;
+use std::str::FromStr;
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use colored::{ColoredString, Colorize};
+use itertools::Itertools;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::rusty_tasks::*;
+use crate::rusty_agenda::{is_overdue, local_today};
+use crate::rusty_args::CommandArgs;
+use crate::rusty_config::settings;
+use crate::rusty_theme::{check_mark, palette, use_unicode};
+
+/// Columns the task list can show
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Column{
+    Number,
+    Status,
+    Priority,
+    Due,
+    Completed,
+    Created,
+    Updated,
+    Recur,
+    Text
+}
+
+impl FromStr for Column{
+    type Err = String;
+    fn from_str(input:&str)->Result<Column,String>{
+        match input.trim().to_lowercase().as_str() {
+            "number"=>Ok(Column::Number),
+            "status"=>Ok(Column::Status),
+            "priority"=>Ok(Column::Priority),
+            "due"=>Ok(Column::Due),
+            "completed"=>Ok(Column::Completed),
+            "created"=>Ok(Column::Created),
+            "updated"=>Ok(Column::Updated),
+            "recur"=>Ok(Column::Recur),
+            "text"=>Ok(Column::Text),
+            other=>Err(format!("'{other}' is not a column, use number, status, priority, due, completed, created, updated, recur or text."))
+        }
+    }
+}
+
+impl Column{
+    fn heading(&self)->&'static str{
+        match self {
+            Column::Number=>"#",
+            Column::Status=>"",
+            Column::Priority=>"Pri",
+            Column::Due=>"Due",
+            Column::Completed=>"Completed",
+            Column::Created=>"Created",
+            Column::Updated=>"Updated",
+            Column::Recur=>"Repeats",
+            Column::Text=>"Task"
+        }
+    }
+
+    /// Date columns are left out when no row has a value, the others always show
+    fn hide_when_empty(&self)->bool{
+        !matches!(self,Column::Number | Column::Status | Column::Text)
+    }
+}
+
+/// Reads a comma separated list of column names
+pub fn parse_columns(input:&str)->Result<Vec<Column>,String>{
+    let columns:Vec<Column>=input.split(',').filter(|c| !c.trim().is_empty()).map(Column::from_str).try_collect()?;
+    if columns.is_empty() {
+        return Err("Choose at least one column.".to_string())
+    }
+    Ok(columns)
+}
+
+/// How a task list is laid out
+#[derive(Debug,Clone,PartialEq)]
+pub struct ListOptions{
+    pub columns: Vec<Column>,
+    pub relative_dates: bool,
+    pub wrap: bool,
+    /// Room for a whole line, indent included
+    pub width: usize,
+    /// Show at most this many Tasks
+    pub limit: Option<usize>,
+    /// Skip this many Tasks first
+    pub offset: usize
+}
+
+impl ListOptions{
+    /// The options from the config, sized to the terminal
+    pub fn from_settings()->ListOptions{
+        let config=settings();
+        ListOptions{
+            columns:parse_columns(&config.list_columns.join(",")).unwrap_or(vec![Column::Number,Column::Status,Column::Text]),
+            relative_dates:config.relative_dates,
+            wrap:config.long_text == "wrap",
+            width:terminal_width(),
+            limit:None,
+            offset:0
+        }
+    }
+
+    /// The config options changed by `--columns="due,text"`, `--relative`, `--absolute`, `--wrap`, `--truncate`,
+    /// `--limit=n` and `--offset=n`
+    pub fn from_args(args:&CommandArgs)->Result<ListOptions,String>{
+        let mut options=ListOptions::from_settings();
+        let number=|key:&str| args.get(key).map(|value| value.parse::<usize>().map_err(|_| format!("--{key} needs a number, not '{value}'."))).transpose();
+        options.limit=number("limit")?;
+        options.offset=number("offset")?.unwrap_or(0);
+        if let Some(columns)=args.get("columns") {
+            options.columns=parse_columns(columns)?;
+        }
+        if args.has_flag("relative") { options.relative_dates=true; }
+        if args.has_flag("absolute") { options.relative_dates=false; }
+        if args.has_flag("wrap") { options.wrap=true; }
+        if args.has_flag("truncate") { options.wrap=false; }
+        Ok(options)
+    }
+}
+
+/// Columns of the terminal, or of $COLUMNS when output is not a terminal; without either lines are not cut
+pub fn terminal_width()->usize{
+    if std::io::stdout().is_terminal() {
+        if let Ok((cols,_))=crossterm::terminal::size() {
+            return cols as usize
+        }
+    }
+    std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()).unwrap_or(usize::MAX)
+}
+
+/// A date as days from today: "today", "in 2d" or "3d ago"
+pub fn relative_date(date:DateTime<Utc>,today:NaiveDate)->String{
+    let days=(date.with_timezone(&Local).date_naive()-today).num_days();
+    match days {
+        0=>"today".to_string(),
+        1..=i64::MAX=>format!("in {days}d"),
+        _=>format!("{}d ago",-days)
+    }
+}
+
+/// Cuts text to fit `width` columns, marking the cut
+pub fn truncate(text:&str,width:usize)->String{
+    if text.width() <= width {
+        return text.to_string()
+    }
+    let marker=if use_unicode() { "…" } else { "~" };
+    let mut result=String::new();
+    let mut used=0;
+    for c in text.chars(){
+        let w=c.width().unwrap_or(0);
+        if used+w+marker.width() > width {
+            break;
+        }
+        used+=w;
+        result.push(c);
+    }
+    result+marker
+}
+
+/// Breaks text into lines of at most `width` columns, at spaces where it can
+pub fn wrap(text:&str,width:usize)->Vec<String>{
+    let width=width.max(1);
+    let mut lines:Vec<String>=Vec::new();
+    let mut line=String::new();
+    for word in text.split(' '){
+        let mut word=word.to_string();
+        loop{
+            let needed=if line.is_empty() { word.width() } else { line.width()+1+word.width() };
+            if needed <= width {
+                if !line.is_empty() { line.push(' '); }
+                line+=&word;
+                break;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+                continue;
+            }
+            // a word longer than the line is split wherever it has to be
+            let mut head=String::new();
+            let mut rest=word.chars();
+            for c in rest.by_ref(){
+                if head.width()+c.width().unwrap_or(0) > width {
+                    lines.push(std::mem::take(&mut head));
+                    head.push(c);
+                    break;
+                }
+                head.push(c);
+            }
+            word=head+&rest.collect::<String>();
+            if word.width() <= width {
+                line=word;
+                break;
+            }
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+/// Tasks laid out in aligned columns that fit the terminal
+pub struct TaskTable{
+    columns: Vec<Column>,
+    widths: Vec<usize>,
+    cells: Vec<(usize,Vec<String>)>,
+    wrap: bool
+}
+
+impl TaskTable{
+    /// Measures the Tasks at the given indexes, hiding date columns no row uses
+    pub fn new(tl:&TaskList,indexes:&[usize],options:&ListOptions)->TaskTable{
+        let (indent,date_format)={
+            let config=settings();
+            (config.indent,config.list_date_format.clone())
+        };
+        let today=local_today();
+        let format_date=|date:Option<DateTime<Utc>>| match date {
+            Some(date) if options.relative_dates=>relative_date(date,today),
+            Some(date)=>date.with_timezone(&Local).format(&date_format).to_string(),
+            None=>String::new()
+        };
+        let cells:Vec<(usize,Vec<String>)>=indexes.iter()
+            .filter(|i| **i < tl.tasks.len())
+            .map(|i|{
+                let task=&tl.tasks[*i];
+                let open=tl.open_prerequisites(*i);
+                let row=options.columns.iter().map(|column| match column {
+                    Column::Number=>format!("{}",i+1),
+                    Column::Status=>if task.completed { format!("[{}]",check_mark()) } else { "[ ]".to_string() },
+                    Column::Priority=>task.priority.map(|p| format!("({p})")).unwrap_or_default(),
+                    Column::Due=>format_date(task.due_date),
+                    Column::Completed=>format_date(task.completed_date),
+                    Column::Created=>format_date(task.created_at),
+                    Column::Updated=>format_date(task.updated_at),
+                    Column::Recur=>task.recurrence.as_ref().map(|r| r.to_string()).unwrap_or_default(),
+                    Column::Text if open.is_empty() || task.completed=>task.data.clone(),
+                    Column::Text=>format!("{} [Blocked by: {}]",task.data,open.iter().map(|i| format!("#{}",i+1)).join(", "))
+                }).collect();
+                (*i,row)
+            })
+            .collect();
+
+        // keep only the columns that have something to show
+        let keep:Vec<usize>=(0..options.columns.len())
+            .filter(|c| !options.columns[*c].hide_when_empty() || cells.iter().any(|(_,row)| !row[*c].is_empty()))
+            .collect();
+        let columns:Vec<Column>=keep.iter().map(|c| options.columns[*c]).collect();
+        let cells:Vec<(usize,Vec<String>)>=cells.into_iter()
+            .map(|(i,row)| (i,keep.iter().map(|c| row[*c].clone()).collect()))
+            .collect();
+
+        let mut widths:Vec<usize>=columns.iter().enumerate()
+            .map(|(c,column)| cells.iter().map(|(_,row)| row[c].width()).chain([column.heading().width()]).max().unwrap_or(0))
+            .collect();
+        // the text column gets whatever room the others leave, but never less than 10
+        if let Some(text)=columns.iter().position(|c| *c == Column::Text) {
+            let others:usize=widths.iter().enumerate().filter(|(c,_)| *c != text).map(|(_,w)| w+2).sum();
+            let room=options.width.saturating_sub(indent+others).max(10);
+            widths[text]=widths[text].min(room);
+        }
+        TaskTable{ columns, widths, cells, wrap:options.wrap }
+    }
+
+    /// The line of column headings
+    pub fn header(&self)->String{
+        let headings:Vec<String>=self.columns.iter().map(|c| c.heading().to_string()).collect();
+        self.join(&headings,&|_,text| text.color(palette().muted))
+    }
+
+    /// The lines of the Task at index, more than one when its text wraps
+    pub fn rows(&self,tl:&TaskList,index:usize)->Vec<String>{
+        let Some((_,row))=self.cells.iter().find(|(i,_)| *i == index) else { return Vec::new() };
+        let task=&tl.tasks[index];
+        let colors=palette();
+        let paint=|column:Column,text:String|->ColoredString{
+            match column {
+                Column::Number if tl.is_blocked(index) && !task.completed=>text.color(colors.blocked),
+                Column::Number=>text.color(colors.muted),
+                Column::Status if task.completed=>text.color(colors.done),
+                Column::Status=>text.color(colors.todo),
+                Column::Priority=>text.color(colors.label).bold(),
+                Column::Due if is_overdue(task)=>text.color(colors.overdue).bold(),
+                Column::Due=>text.color(colors.due),
+                Column::Completed=>text.color(colors.completed),
+                Column::Created | Column::Updated=>text.color(colors.muted),
+                Column::Recur=>text.color(colors.recurrence),
+                Column::Text if task.completed=>text.strikethrough().color(colors.muted),
+                Column::Text=>text.color(colors.text)
+            }
+        };
+
+        // every cell as lines, only the text column ever has more than one
+        let lines:Vec<Vec<String>>=self.columns.iter().zip(row).zip(&self.widths)
+            .map(|((column,text),width)| match column {
+                Column::Text if self.wrap=>wrap(text,*width),
+                _=>vec![truncate(text,*width)]
+            })
+            .collect();
+        let height=lines.iter().map(|l| l.len()).max().unwrap_or(1);
+        (0..height)
+            .map(|n|{
+                let cells:Vec<String>=lines.iter().map(|l| l.get(n).cloned().unwrap_or_default()).collect();
+                self.join(&cells,&|c,text| paint(self.columns[c],text))
+            })
+            .collect()
+    }
+
+    /// Pads every cell but the last to its column's width and colours it
+    fn join(&self,cells:&[String],paint:&dyn Fn(usize,String)->ColoredString)->String{
+        let last=cells.len().saturating_sub(1);
+        cells.iter().enumerate()
+            .map(|(c,text)|{
+                let pad=if c == last { 0 } else { self.widths[c].saturating_sub(text.width()) };
+                // numbers line up on the right
+                let (left,right)=if self.columns[c] == Column::Number { (pad,0) } else { (0,pad) };
+                format!("{}{}{}"," ".repeat(left),paint(c,text.clone())," ".repeat(right))
+            })
+            .join("  ")
+            .trim_end()
+            .to_string()
+    }
+}
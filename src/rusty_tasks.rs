@@ -1,287 +1,950 @@
-use std::{fmt, str::FromStr};
-use chrono::{DateTime, Local, Utc};
-use colored::Colorize;
-use itertools::Itertools;
-use regex::Regex;
-
-/// Represents a task with a completion status and associated data.
-#[derive(Default, Debug,Clone)]
-pub struct Task{
-    pub completed: bool,
-    pub data: String,
-    pub due_date: Option<DateTime<Utc>>,
-    pub completed_date: Option<DateTime<Utc>>
-}
-
-/// Implements a default Display formatter for Tasks
-impl fmt::Display for Task{
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let string_completed = match self.completed {
-            true=>"[√]".green(),
-            false=>"[ ]".red()
-        };
-        let struct_string="Task ->".color("purple");
-        let formatted_data=match self.completed{
-            true=>self.data.strikethrough().truecolor(125,125,125),
-            false=>self.data.color("white")
-        };
-        let due_date = match self.due_date {
-            Some(value) => value.with_timezone(&Local).to_string().yellow(),
-            None=>{"N/A".to_string().truecolor(125,125,125)}
-        };
-        let completed_date=match self.completed_date{
-            Some(value) => value.with_timezone(&Local).to_string().green(),
-            None=>{"N/A".to_string().green()}
-        };
-        write!(f,"{} {} [Due: {}] [Completed: {}] {}",struct_string,string_completed,due_date,completed_date,formatted_data)
-    }
-}
-
-/// Implements a constructor for Tasks
-impl Task{
-    pub fn new(c:bool,d:String)->Task{
-        Task{
-            completed:c,
-            data:d,
-            ..Default::default()
-        }
-    }
-}
-
-/// Represents a list of Tasks
-#[derive(Debug,Clone)]
-pub struct TaskList {
-    pub tasks: Vec<Task>,
-}
-
-/// Implements Task management methods for TaskList
-impl TaskList{
-    pub fn add_task(&mut self,mytask:Task)->Result<usize,String>{         
-        let veclen=self.tasks.len();      
-        self.tasks.push(mytask);
-        if veclen >= self.tasks.len() {
-            return Err("Push failed.".to_string())
-        }
-        Ok(self.tasks.len() - 1)
-    }
-
-    pub fn delete_task(&mut self,index:usize)->Result<(),String>{
-        if index < self.tasks.len() {
-            self.tasks.remove(index);
-            return Ok(())
-        }
-        Err("Invalid index.".to_string())
-    }
-
-    pub fn toggle_completed_task(&mut self,index:usize)->Result<(),String>{
-        if index < self.tasks.len() {
-    
-            self.tasks[index].completed = !self.tasks[index].completed;
-        
-            return Ok(())
-        }
-        Err("Invalid index.".to_string())
-    }
-
-    #[allow(dead_code)]
-    pub fn print(&self){
-        println!("    Tasks: \r\n {:?}",self.tasks.iter().enumerate().format("\r\n "))
-    }
-
-    #[allow(dead_code)]
-    pub fn print_pretty(&self){
-        let eol="\r\n";
-        let indent=4;
-        let spacing = " ".repeat(indent);
-        let result=self.tasks
-        .iter()
-        .enumerate()
-        .map(|(i,v)| {
-            let n=i+1;
-            format!("{n}: {v}")}
-        )
-        .join(format!("\r\n{spacing}").as_str());
-        
-        let struct_string="Tasks: ".color("purple");
-
-        println!("{spacing}{struct_string}{eol}{spacing}{result}{eol}{spacing}");
-    }
-}
-
-/// Represents Task Commands user is able to input.
-#[derive(Default,Debug,Clone)]
-pub enum TASKCOM{
-    #[default]
-    Help,
-    List,
-    Add,
-    Remove,
-    Complete,
-    Exit,
-    Unknown
-}
-
-impl FromStr for TASKCOM{
-    type Err = ();
-    fn from_str(input: &str) -> Result<TASKCOM, Self::Err> {
-        match input {
-            "HELP"  => Ok(TASKCOM::Help),
-            "LIST"  => Ok(TASKCOM::List),
-            "ADD"  => Ok(TASKCOM::Add),
-            "REMOVE" => Ok(TASKCOM::Remove),
-            "COMPLETE" => Ok(TASKCOM::Complete),
-            "EXIT" => Ok(TASKCOM::Exit),
-            "UNKNOWN" => Ok(TASKCOM::Unknown),
-            _      => Err(()),
-        }
-    }
-}
-
-impl fmt::Display for TASKCOM{
-    fn fmt(&self, f: &mut fmt::Formatter)->fmt::Result{
-        let com_string=match &self{
-            TASKCOM::Help=>"HELP",
-            TASKCOM::List=>"LIST",
-            TASKCOM::Add=>"ADD",
-            TASKCOM::Remove=>"REMOVE",
-            TASKCOM::Complete=>"COMPLETE",
-            TASKCOM::Exit=>"EXIT",
-            TASKCOM::Unknown=>"UNKNOWN"
-        };
-        write!(f,"{com_string}")
-    }
-}
-
-impl TASKCOM {
-    /// When you want ALL values TASKCOM can make
-    #[allow(dead_code)]
-    pub fn into_iter() -> core::array::IntoIter<TASKCOM, 7> {
-        [
-            TASKCOM::Help,
-            TASKCOM::List,
-            TASKCOM::Add,
-            TASKCOM::Remove,
-            TASKCOM::Complete,
-            TASKCOM::Exit,
-            TASKCOM::Unknown
-        ]
-        .into_iter()
-    }
-
-    /// When you want to print out commands for the user
-    pub fn into_iter_client() -> core::array::IntoIter<TASKCOM, 6> {
-        [
-            TASKCOM::Help,
-            TASKCOM::List,
-            TASKCOM::Add,
-            TASKCOM::Remove,
-            TASKCOM::Complete,
-            TASKCOM::Exit,
-        ]
-        .into_iter()
-
-    }
-}
-
-/// Lists all commands available in a TASKCOM
-pub fn list_task_commands()->colored::ColoredString{
-    let mut result:String=String::new();
-    for command in TASKCOM::into_iter_client(){
-            result+=format!("{command} ").as_str();        
-    }    
-    result.trim_end()
-        .split(" ")
-        .join(", ")
-        .green()
-}
-
-/// Convert tasklist to string
-pub fn convert_tltostring(tl:TaskList)->String{
-let eol = "\r\n";
-let mut result = String::new();
-result += eol;
-result += format!("# TaskList:{eol}").as_str();
-for task in tl.tasks{
-    let tdata=task.data;
-    let tcompleted=task.completed;
-    let tdue_date=match task.due_date{
-        Some(value)=>value.with_timezone(&Local).to_string(),
-        None=>"".to_string()
-    };
-    let tcompleted_date=match task.completed_date{
-        Some(value)=>value.with_timezone(&Local).to_string(),
-        None=>"".to_string()
-    };
-    result += format!(" - ").as_str();
-    result += match tcompleted{
-        true => "[√]",
-        false => "[ ]"
-    };
-    result += format!(" [Due: {tdue_date}]").as_str();
-    result += format!(" [Completed: {tcompleted_date}]").as_str();
-    result += format!(" {tdata}").as_str();
-    //result += tcompleted.to_string().as_str();
-    result += eol;
-}
-
-result
-}
-
-/// Convert string to tasklist
-pub fn convert_stringtotl(data:String)->TaskList{
-//println!("CONVERT STRING TO TL DATAIN:{}",data);
-let lines = data.split("\r\n");
-let mut tl:TaskList=TaskList{ tasks: Vec::new() };
-let mut tlfound=false;
-for line in lines{ 
-    if line.contains("# TaskList:"){
-        tlfound=true;
-    }
-    if tlfound{//even AFTER the line detected, this allows rest of code to run because its saved outside loop
-        // convert - lines into Tasks   
-        let re_full = Regex::new(r" - (\[[ √]\]) \[Due: (.*?)\] \[Completed: (.*?)\] (.*)").unwrap();
-        let _re_simple = Regex::new(r" - (\[[ √]\]) (.*)");
-        
-        let temp_task = match re_full.captures(line){
-            Some(captures)=>captures,
-            None=>continue //skip rest of loop
-        };
-        
-        let tcompleted_string:String=temp_task[1].to_string();
-        let tdue_date:String=temp_task[2].to_string();
-        let tcompleted_date:String=temp_task[3].to_string();
-        let tdata:String=temp_task[4].to_string();
-        
-        // convert brackets into completed/uncompleted
-        let tcompleted:bool = tcompleted_string.contains("[√]");
-
-        // build task
-        let mut new_task=Task::new(tcompleted,tdata);
-
-        // date management
-        // always convert from LOCAL string, to UTC struct
-        let default_date_format="%Y-%m-%d %H:%M:%S %z";
-        
-        new_task.due_date=match DateTime::parse_from_str(tdue_date.as_str(), default_date_format){
-            Ok(value)=>Some(value.to_utc()),
-            Err(_)=>None
-        };
-        new_task.completed_date=match DateTime::parse_from_str(tcompleted_date.as_str(), default_date_format){
-            Ok(value)=>Some(value.to_utc()),
-            Err(_)=>None
-        };
-
-        // correct disparity between CHECK completed and COMPLETED date
-        if new_task.completed_date == None {
-            if tcompleted == true {
-                new_task.completed_date = Some(Utc::now())
-            }
-        }
-
-        // task building complete
-        tl.tasks.push(new_task);
-    }
-}
-// Return TaskList, if one was not found we return an empty TaskList
-tl
+use std::{collections::HashMap, fmt, str::FromStr};
+use chrono::{DateTime, Local, Utc};
+use colored::Colorize;
+use itertools::Itertools;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::rusty_recurrence::Recurrence;
+use crate::rusty_agenda::is_overdue;
+use crate::rusty_config::settings;
+use crate::rusty_theme::{check_mark, palette, Palette};
+use crate::rusty_table::{ListOptions, TaskTable};
+use crate::rusty_pager::print_paged;
+use crate::rusty_migrate::markdown_version;
+
+/// Represents a task with a completion status and associated data.
+/// JSON and YAML files store it field by field, leaving out the empty ones.
+#[derive(Default, Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(default)]
+pub struct Task{
+    pub id: usize,
+    pub completed: bool,
+    pub data: String,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub due_date: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub completed_date: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if="Vec::is_empty")]
+    pub depends_on: Vec<usize>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub recurrence: Option<Recurrence>,
+    /// A to Z, A is the most important
+    #[serde(skip_serializing_if="Option::is_none",deserialize_with="deserialize_priority")]
+    pub priority: Option<char>,
+    #[serde(skip_serializing_if="Vec::is_empty")]
+    pub notes: Vec<String>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// The UID of a Task imported from a calendar, exported again so the calendar sees the same task
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub uid: Option<String>
+}
+
+/// Implements a default Display formatter for Tasks
+impl fmt::Display for Task{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let config=settings();
+        let colors=Palette::from_config(&config);
+        let format_date=|value:DateTime<Utc>| value.with_timezone(&Local).format(&config.date_format).to_string();
+        let string_completed = match self.completed {
+            true=>format!("[{}]",check_mark()).color(colors.done),
+            false=>"[ ]".color(colors.todo)
+        };
+        let struct_string="Task ->".color(colors.label);
+        let formatted_data=match self.completed{
+            true=>self.data.strikethrough().color(colors.muted),
+            false=>self.data.color(colors.text)
+        };
+        let due_date = match self.due_date {
+            Some(value) if is_overdue(self) => format_date(value).color(colors.overdue).bold(),
+            Some(value) => format_date(value).color(colors.due),
+            None=>{"N/A".to_string().color(colors.muted)}
+        };
+        let completed_date=match self.completed_date{
+            Some(value) => format_date(value).color(colors.completed),
+            None=>{"N/A".to_string().color(colors.completed)}
+        };
+        write!(f,"{} {} [Due: {}] [Completed: {}] ",struct_string,string_completed,due_date,completed_date)?;
+        if let Some(rule)=&self.recurrence {
+            write!(f,"{} ",format!("[Repeats: {rule}]").color(colors.recurrence))?;
+        }
+        if let Some(priority)=self.priority {
+            write!(f,"{} ",format!("({priority})").color(colors.label))?;
+        }
+        write!(f,"{}",formatted_data)
+    }
+}
+
+/// Implements a constructor for Tasks
+impl Task{
+    pub fn new(c:bool,d:String)->Task{
+        Task{
+            completed:c,
+            data:d,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the #tag, +project and @context words in the Task text
+    pub fn tags(&self)->Vec<String>{
+        self.data
+            .split_whitespace()
+            .filter(|word| word.len() > 1 && word.starts_with(['#','+','@']))
+            .map(|word| word.trim_end_matches([',','.',';',':','!','?']).to_string())
+            .collect()
+    }
+}
+
+/// Represents a list of Tasks
+#[derive(Debug,Clone,Serialize,Deserialize)]
+pub struct TaskList {
+    pub tasks: Vec<Task>,
+}
+
+/// Implements Task management methods for TaskList
+impl TaskList{
+    pub fn add_task(&mut self,mut mytask:Task)->Result<usize,String>{         
+        let veclen=self.tasks.len();      
+        if mytask.id == 0 || self.index_of_id(mytask.id).is_some() {
+            mytask.id=self.next_id();
+        }
+        let now=Utc::now();
+        mytask.created_at.get_or_insert(now);
+        mytask.updated_at=Some(now);
+        self.tasks.push(mytask);
+        if veclen >= self.tasks.len() {
+            return Err("Push failed.".to_string())
+        }
+        Ok(self.tasks.len() - 1)
+    }
+
+    pub fn delete_task(&mut self,index:usize)->Result<(),String>{
+        if index < self.tasks.len() {
+            let removed=self.tasks.remove(index);
+            // drop links pointing at the removed task so nothing stays blocked on it
+            for task in self.tasks.iter_mut(){
+                task.depends_on.retain(|id| *id != removed.id);
+            }
+            return Ok(())
+        }
+        Err("Invalid index.".to_string())
+    }
+
+    pub fn toggle_completed_task(&mut self,index:usize)->Result<(),String>{
+        if index < self.tasks.len() {
+    
+            self.tasks[index].completed = !self.tasks[index].completed;
+            self.touch(index);
+        
+            return Ok(())
+        }
+        Err("Invalid index.".to_string())
+    }
+
+    /// Replaces the text of the Task at index
+    pub fn set_task_data(&mut self,index:usize,data:String)->Result<(),String>{
+        match self.tasks.get_mut(index){
+            Some(task)=>{
+                task.data=data;
+                self.touch(index);
+                Ok(())
+            },
+            None=>Err("Invalid index.".to_string())
+        }
+    }
+
+    /// Marks the Task at index as modified now
+    pub fn touch(&mut self,index:usize){
+        if let Some(task)=self.tasks.get_mut(index){
+            let now=Utc::now();
+            task.created_at.get_or_insert(now);
+            task.updated_at=Some(now);
+        }
+    }
+
+    /// Fills in missing created/updated times for Tasks from files that predate them.
+    /// `fallback` is the best known time the Task already existed, like the file's modified time.
+    pub fn backfill_timestamps(&mut self,fallback:DateTime<Utc>){
+        for task in self.tasks.iter_mut(){
+            let known=[task.completed_date,task.updated_at,Some(fallback)];
+            let created=*task.created_at.get_or_insert(known.iter().flatten().min().copied().unwrap_or(fallback));
+            let updated=task.updated_at.get_or_insert(task.completed_date.unwrap_or(created));
+            if *updated < created {
+                *updated=created;
+            }
+        }
+    }
+
+    /// Returns a date field of the Task at index by name, for filters and sorts
+    pub fn date_field(&self,index:usize,field:&str)->Result<Option<DateTime<Utc>>,String>{
+        let task=self.tasks.get(index).ok_or("Invalid index.".to_string())?;
+        match field {
+            "created"=>Ok(task.created_at),
+            "updated"=>Ok(task.updated_at),
+            "due"=>Ok(task.due_date),
+            "completed"=>Ok(task.completed_date),
+            _=>Err(format!("Unknown date field '{field}', use created, updated, due or completed."))
+        }
+    }
+
+    /// Reorders Tasks by a field; Tasks without a value for a date field go last
+    pub fn sort_by_field(&mut self,field:&str,descending:bool)->Result<(),String>{
+        if field == "text" {
+            self.tasks.sort_by_key(|t| t.data.to_lowercase());
+            if descending {
+                self.tasks.reverse();
+            }
+            return Ok(())
+        }
+        let mut keyed:Vec<(Option<DateTime<Utc>>,Task)>=Vec::new();
+        for i in 0..self.tasks.len(){
+            keyed.push((self.date_field(i,field)?,self.tasks[i].clone()));
+        }
+        keyed.sort_by(|(a,_),(b,_)| match (a,b) {
+            (Some(a),Some(b))=>if descending { b.cmp(a) } else { a.cmp(b) },
+            (Some(_),None)=>std::cmp::Ordering::Less,
+            (None,Some(_))=>std::cmp::Ordering::Greater,
+            (None,None)=>std::cmp::Ordering::Equal
+        });
+        self.tasks=keyed.into_iter().map(|(_,task)| task).collect();
+        Ok(())
+    }
+
+    /// Returns the indexes of Tasks whose date field is before (or after) the given time
+    pub fn filter_by_date(&self,field:&str,before:bool,when:DateTime<Utc>)->Result<Vec<usize>,String>{
+        let mut result=Vec::new();
+        for i in 0..self.tasks.len(){
+            if let Some(value)=self.date_field(i,field)? {
+                if (before && value < when) || (!before && value > when) {
+                    result.push(i);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns the next unused stable Task id
+    pub fn next_id(&self)->usize{
+        self.tasks.iter().map(|t| t.id).max().unwrap_or(0)+1
+    }
+
+    /// Finds the current position of a Task by its stable id
+    pub fn index_of_id(&self,id:usize)->Option<usize>{
+        self.tasks.iter().position(|t| t.id == id)
+    }
+
+    /// Gives every Task a unique id, used after loading files that predate ids
+    pub fn ensure_ids(&mut self){
+        let mut seen:Vec<usize>=Vec::new();
+        let mut next=self.next_id();
+        for task in self.tasks.iter_mut(){
+            if task.id == 0 || seen.contains(&task.id) {
+                task.id=next;
+                next+=1;
+            }
+            seen.push(task.id);
+        }
+    }
+
+    /// Makes the Task at index depend on the Task at prerequisite, rejecting cycles
+    pub fn add_dependency(&mut self,index:usize,prerequisite:usize)->Result<(),String>{
+        if index >= self.tasks.len() || prerequisite >= self.tasks.len() {
+            return Err("Invalid index.".to_string())
+        }
+        if index == prerequisite {
+            return Err("A task cannot depend on itself.".to_string())
+        }
+        let task_id=self.tasks[index].id;
+        let prerequisite_id=self.tasks[prerequisite].id;
+        if self.tasks[index].depends_on.contains(&prerequisite_id) {
+            return Ok(())
+        }
+        if self.depends_on_transitively(prerequisite_id,task_id) {
+            return Err("Dependency would create a cycle.".to_string())
+        }
+        self.tasks[index].depends_on.push(prerequisite_id);
+        self.touch(index);
+        Ok(())
+    }
+
+    /// Removes the link between the Task at index and the Task at prerequisite
+    pub fn remove_dependency(&mut self,index:usize,prerequisite:usize)->Result<(),String>{
+        if index >= self.tasks.len() || prerequisite >= self.tasks.len() {
+            return Err("Invalid index.".to_string())
+        }
+        let prerequisite_id=self.tasks[prerequisite].id;
+        let deps=&mut self.tasks[index].depends_on;
+        if !deps.contains(&prerequisite_id) {
+            return Err("Task does not depend on that task.".to_string())
+        }
+        deps.retain(|id| *id != prerequisite_id);
+        self.touch(index);
+        Ok(())
+    }
+
+    /// True when the Task with id `from` reaches the Task with id `to` through its dependencies
+    fn depends_on_transitively(&self,from:usize,to:usize)->bool{
+        let mut stack=vec![from];
+        let mut visited:Vec<usize>=Vec::new();
+        while let Some(id)=stack.pop(){
+            if id == to {
+                return true
+            }
+            if visited.contains(&id) {
+                continue
+            }
+            visited.push(id);
+            if let Some(i)=self.index_of_id(id){
+                stack.extend(self.tasks[i].depends_on.iter().copied());
+            }
+        }
+        false
+    }
+
+    /// Lists problems that would make the TaskList inconsistent: duplicate ids, unknown or cyclic dependencies
+    pub fn validate(&self)->Vec<String>{
+        let mut problems=Vec::new();
+        for (i,task) in self.tasks.iter().enumerate(){
+            let n=i+1;
+            if self.tasks.iter().filter(|t| t.id == task.id).count() > 1 {
+                problems.push(format!("Task {n} shares id {} with another task.",task.id));
+            }
+            for id in &task.depends_on{
+                if self.index_of_id(*id).is_none() {
+                    problems.push(format!("Task {n} depends on unknown id {id}."));
+                }else if self.depends_on_transitively(*id,task.id) {
+                    problems.push(format!("Task {n} is part of a dependency cycle."));
+                }
+            }
+        }
+        problems.dedup();
+        problems
+    }
+
+    /// Returns the indexes of uncompleted Tasks the Task at index is waiting on
+    pub fn open_prerequisites(&self,index:usize)->Vec<usize>{
+        match self.tasks.get(index){
+            Some(task)=>task.depends_on
+                .iter()
+                .filter_map(|id| self.index_of_id(*id))
+                .filter(|i| !self.tasks[*i].completed)
+                .collect(),
+            None=>Vec::new()
+        }
+    }
+
+    /// A Task is blocked while any of its prerequisites are still open
+    pub fn is_blocked(&self,index:usize)->bool{
+        !self.open_prerequisites(index).is_empty()
+    }
+
+    /// Returns the indexes of Tasks that are not completed and not blocked
+    pub fn actionable_tasks(&self)->Vec<usize>{
+        (0..self.tasks.len())
+            .filter(|i| !self.tasks[*i].completed && !self.is_blocked(*i))
+            .collect()
+    }
+
+    /// Adds the next instance of a completed recurring Task and returns its index.
+    /// The completed Task keeps its place as the log of that occurrence and stops repeating.
+    pub fn spawn_next_recurrence(&mut self,index:usize)->Option<usize>{
+        let task=self.tasks.get_mut(index)?;
+        if !task.completed {
+            return None
+        }
+        let rule=task.recurrence.take()?;
+        let completed_date=*task.completed_date.get_or_insert_with(Utc::now);
+        let mut next_task=Task::new(false,task.data.clone());
+        next_task.due_date=Some(rule.next_due(task.due_date,completed_date));
+        next_task.depends_on=task.depends_on.clone();
+        next_task.recurrence=Some(rule);
+        self.add_task(next_task).ok()
+    }
+
+    /// Prints every field and note of the Task at index
+    pub fn print_detail(&self,index:usize){
+        let Some(task)=self.tasks.get(index) else { return };
+        let eol="\r\n";
+        let config=settings();
+        let indent=config.indent;
+        let spacing = " ".repeat(indent);
+        let colors=Palette::from_config(&config);
+        let label=|text:&str| format!("{text}:").color(colors.label);
+        let format_date=|date:Option<DateTime<Utc>>| date.map(|d| d.with_timezone(&Local).format(&config.date_format).to_string()).unwrap_or("N/A".to_string());
+
+        let mut result=format!("{spacing}{} {}{eol}",label("Task"),index+1);
+        result+=&format!("{spacing}{} {}{eol}",label("Id"),task.id);
+        result+=&format!("{spacing}{} {}{eol}",label("Text"),task.data);
+        result+=&format!("{spacing}{} {}{eol}",label("Completed"),if task.completed {"yes"} else {"no"});
+        result+=&format!("{spacing}{} {}{eol}",label("Due"),format_date(task.due_date));
+        result+=&format!("{spacing}{} {}{eol}",label("Completed on"),format_date(task.completed_date));
+        result+=&format!("{spacing}{} {}{eol}",label("Created"),format_date(task.created_at));
+        result+=&format!("{spacing}{} {}{eol}",label("Updated"),format_date(task.updated_at));
+        if let Some(rule)=&task.recurrence {
+            result+=&format!("{spacing}{} {rule}{eol}",label("Repeats"));
+        }
+        if let Some(priority)=task.priority {
+            result+=&format!("{spacing}{} {priority}{eol}",label("Priority"));
+        }
+        if !task.depends_on.is_empty() {
+            let deps=task.depends_on.iter()
+                .map(|id| match self.index_of_id(*id){
+                    Some(i)=>format!("#{}",i+1),
+                    None=>format!("missing id {id}")
+                })
+                .join(", ");
+            result+=&format!("{spacing}{} {deps}{eol}",label("Depends on"));
+        }
+        if self.is_blocked(index) && !task.completed {
+            result+=&format!("{spacing}{}{eol}","Blocked".color(colors.blocked));
+        }
+        result+=&format!("{spacing}{}{eol}",label("Notes"));
+        if task.notes.is_empty() {
+            result+=&format!("{spacing}{spacing}{}{eol}","N/A".color(colors.muted));
+        }
+        for note in &task.notes{
+            result+=&format!("{spacing}{spacing}{note}{eol}");
+        }
+        println!("{eol}{result}");
+    }
+
+    #[allow(dead_code)]
+    pub fn print(&self){
+        println!("    Tasks: \r\n {:?}",self.tasks.iter().enumerate().format("\r\n "))
+    }
+
+    #[allow(dead_code)]
+    pub fn print_pretty(&self){
+        let all:Vec<usize>=(0..self.tasks.len()).collect();
+        self.print_selection("Tasks: ",&all);
+    }
+
+    /// Prints only the Tasks at the given indexes, keeping their list numbers
+    pub fn print_selection(&self,title:&str,indexes:&[usize]){
+        self.print_table(title,indexes,&ListOptions::from_settings());
+    }
+
+    /// Prints the Tasks at the given indexes as columns laid out by `options`, paged when they don't fit the screen
+    pub fn print_table(&self,title:&str,indexes:&[usize],options:&ListOptions){
+        print_paged(&self.render_table(title,indexes,options));
+    }
+
+    /// The Tasks at the given indexes as columns, with --offset and --limit applied and a note when some are left out
+    pub fn render_table(&self,title:&str,indexes:&[usize],options:&ListOptions)->String{
+        let eol="\r\n";
+        let indent=settings().indent;
+        let spacing = " ".repeat(indent);
+        let shown:Vec<usize>=indexes.iter().skip(options.offset).take(options.limit.unwrap_or(usize::MAX)).copied().collect();
+        let table=TaskTable::new(self,&shown,options);
+        let result=std::iter::once(table.header())
+        .chain(shown.iter().flat_map(|i| table.rows(self,*i)))
+        .join(format!("\r\n{spacing}").as_str());
+        
+        let struct_string=title.color(palette().label);
+        let mut footer=String::new();
+        if shown.is_empty() && !indexes.is_empty() {
+            footer=format!("{}{eol}{spacing}",format!("Nothing after --offset={}, there are {}.",options.offset,indexes.len()).color(palette().muted));
+        }else if shown.len() < indexes.len() {
+            let first=options.offset+1;
+            let last=options.offset+shown.len();
+            let more=if last < indexes.len() { format!(", --offset={last} shows the next") } else { String::new() };
+            footer=format!("{}{eol}{spacing}",format!("Showing {first}-{last} of {}{more}.",indexes.len()).color(palette().muted));
+        }
+
+        format!("{spacing}{struct_string}{eol}{spacing}{result}{eol}{spacing}{footer}")
+    }
+}
+
+/// Represents Task Commands user is able to input.
+#[derive(Default,Debug,Clone)]
+pub enum TASKCOM{
+    #[default]
+    Help,
+    List,
+    Add,
+    Remove,
+    Complete,
+    Depend,
+    Undepend,
+    Next,
+    Recur,
+    Note,
+    Show,
+    EditInEditor,
+    Sort,
+    Filter,
+    Agenda,
+    Today,
+    Overdue,
+    Upcoming,
+    Calendar,
+    Edit,
+    Tui,
+    Load,
+    SaveAs,
+    Config,
+    Import,
+    Export,
+    Convert,
+    Check,
+    Lists,
+    Use,
+    NewList,
+    RenameList,
+    DeleteList,
+    Move,
+    Copy,
+    Exit,
+    Unknown
+}
+
+impl FromStr for TASKCOM{
+    type Err = ();
+    fn from_str(input: &str) -> Result<TASKCOM, Self::Err> {
+        match input {
+            "HELP"  => Ok(TASKCOM::Help),
+            "LIST"  => Ok(TASKCOM::List),
+            "ADD"  => Ok(TASKCOM::Add),
+            "REMOVE" => Ok(TASKCOM::Remove),
+            "COMPLETE" => Ok(TASKCOM::Complete),
+            "DEPEND" => Ok(TASKCOM::Depend),
+            "UNDEPEND" => Ok(TASKCOM::Undepend),
+            "NEXT" => Ok(TASKCOM::Next),
+            "RECUR" => Ok(TASKCOM::Recur),
+            "NOTE" => Ok(TASKCOM::Note),
+            "SHOW" => Ok(TASKCOM::Show),
+            "EDIT-IN-EDITOR" => Ok(TASKCOM::EditInEditor),
+            "SORT" => Ok(TASKCOM::Sort),
+            "FILTER" => Ok(TASKCOM::Filter),
+            "AGENDA" => Ok(TASKCOM::Agenda),
+            "TODAY" => Ok(TASKCOM::Today),
+            "OVERDUE" => Ok(TASKCOM::Overdue),
+            "UPCOMING" => Ok(TASKCOM::Upcoming),
+            "CALENDAR" => Ok(TASKCOM::Calendar),
+            "EDIT" => Ok(TASKCOM::Edit),
+            "TUI" => Ok(TASKCOM::Tui),
+            "LOAD" => Ok(TASKCOM::Load),
+            "SAVEAS" => Ok(TASKCOM::SaveAs),
+            "CONFIG" => Ok(TASKCOM::Config),
+            "IMPORT" => Ok(TASKCOM::Import),
+            "EXPORT" => Ok(TASKCOM::Export),
+            "CONVERT" => Ok(TASKCOM::Convert),
+            "CHECK" => Ok(TASKCOM::Check),
+            "LISTS" => Ok(TASKCOM::Lists),
+            "USE" => Ok(TASKCOM::Use),
+            "NEW-LIST" => Ok(TASKCOM::NewList),
+            "RENAME-LIST" => Ok(TASKCOM::RenameList),
+            "DELETE-LIST" => Ok(TASKCOM::DeleteList),
+            "MOVE" => Ok(TASKCOM::Move),
+            "COPY" => Ok(TASKCOM::Copy),
+            "EXIT" => Ok(TASKCOM::Exit),
+            "UNKNOWN" => Ok(TASKCOM::Unknown),
+            _      => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for TASKCOM{
+    fn fmt(&self, f: &mut fmt::Formatter)->fmt::Result{
+        let com_string=match &self{
+            TASKCOM::Help=>"HELP",
+            TASKCOM::List=>"LIST",
+            TASKCOM::Add=>"ADD",
+            TASKCOM::Remove=>"REMOVE",
+            TASKCOM::Complete=>"COMPLETE",
+            TASKCOM::Depend=>"DEPEND",
+            TASKCOM::Undepend=>"UNDEPEND",
+            TASKCOM::Next=>"NEXT",
+            TASKCOM::Recur=>"RECUR",
+            TASKCOM::Note=>"NOTE",
+            TASKCOM::Show=>"SHOW",
+            TASKCOM::EditInEditor=>"EDIT-IN-EDITOR",
+            TASKCOM::Sort=>"SORT",
+            TASKCOM::Filter=>"FILTER",
+            TASKCOM::Agenda=>"AGENDA",
+            TASKCOM::Today=>"TODAY",
+            TASKCOM::Overdue=>"OVERDUE",
+            TASKCOM::Upcoming=>"UPCOMING",
+            TASKCOM::Calendar=>"CALENDAR",
+            TASKCOM::Edit=>"EDIT",
+            TASKCOM::Tui=>"TUI",
+            TASKCOM::Load=>"LOAD",
+            TASKCOM::SaveAs=>"SAVEAS",
+            TASKCOM::Config=>"CONFIG",
+            TASKCOM::Import=>"IMPORT",
+            TASKCOM::Export=>"EXPORT",
+            TASKCOM::Convert=>"CONVERT",
+            TASKCOM::Check=>"CHECK",
+            TASKCOM::Lists=>"LISTS",
+            TASKCOM::Use=>"USE",
+            TASKCOM::NewList=>"NEW-LIST",
+            TASKCOM::RenameList=>"RENAME-LIST",
+            TASKCOM::DeleteList=>"DELETE-LIST",
+            TASKCOM::Move=>"MOVE",
+            TASKCOM::Copy=>"COPY",
+            TASKCOM::Exit=>"EXIT",
+            TASKCOM::Unknown=>"UNKNOWN"
+        };
+        write!(f,"{com_string}")
+    }
+}
+
+impl TASKCOM {
+    /// When you want ALL values TASKCOM can make
+    #[allow(dead_code)]
+    pub fn into_iter() -> core::array::IntoIter<TASKCOM, 37> {
+        [
+            TASKCOM::Help,
+            TASKCOM::List,
+            TASKCOM::Add,
+            TASKCOM::Remove,
+            TASKCOM::Complete,
+            TASKCOM::Depend,
+            TASKCOM::Undepend,
+            TASKCOM::Next,
+            TASKCOM::Recur,
+            TASKCOM::Note,
+            TASKCOM::Show,
+            TASKCOM::EditInEditor,
+            TASKCOM::Sort,
+            TASKCOM::Filter,
+            TASKCOM::Agenda,
+            TASKCOM::Today,
+            TASKCOM::Overdue,
+            TASKCOM::Upcoming,
+            TASKCOM::Calendar,
+            TASKCOM::Edit,
+            TASKCOM::Tui,
+            TASKCOM::Load,
+            TASKCOM::SaveAs,
+            TASKCOM::Config,
+            TASKCOM::Import,
+            TASKCOM::Export,
+            TASKCOM::Convert,
+            TASKCOM::Check,
+            TASKCOM::Lists,
+            TASKCOM::Use,
+            TASKCOM::NewList,
+            TASKCOM::RenameList,
+            TASKCOM::DeleteList,
+            TASKCOM::Move,
+            TASKCOM::Copy,
+            TASKCOM::Exit,
+            TASKCOM::Unknown
+        ]
+        .into_iter()
+    }
+
+    /// When you want to print out commands for the user
+    pub fn into_iter_client() -> core::array::IntoIter<TASKCOM, 36> {
+        [
+            TASKCOM::Help,
+            TASKCOM::List,
+            TASKCOM::Add,
+            TASKCOM::Remove,
+            TASKCOM::Complete,
+            TASKCOM::Depend,
+            TASKCOM::Undepend,
+            TASKCOM::Next,
+            TASKCOM::Recur,
+            TASKCOM::Note,
+            TASKCOM::Show,
+            TASKCOM::EditInEditor,
+            TASKCOM::Sort,
+            TASKCOM::Filter,
+            TASKCOM::Agenda,
+            TASKCOM::Today,
+            TASKCOM::Overdue,
+            TASKCOM::Upcoming,
+            TASKCOM::Calendar,
+            TASKCOM::Edit,
+            TASKCOM::Tui,
+            TASKCOM::Load,
+            TASKCOM::SaveAs,
+            TASKCOM::Config,
+            TASKCOM::Import,
+            TASKCOM::Export,
+            TASKCOM::Convert,
+            TASKCOM::Check,
+            TASKCOM::Lists,
+            TASKCOM::Use,
+            TASKCOM::NewList,
+            TASKCOM::RenameList,
+            TASKCOM::DeleteList,
+            TASKCOM::Move,
+            TASKCOM::Copy,
+            TASKCOM::Exit,
+        ]
+        .into_iter()
+
+    }
+}
+
+/// Lists all commands available in a TASKCOM
+pub fn list_task_commands()->colored::ColoredString{
+    let mut result:String=String::new();
+    for command in TASKCOM::into_iter_client(){
+            result+=format!("{command} ").as_str();        
+    }    
+    result.trim_end()
+        .split(" ")
+        .join(", ")
+        .green()
+}
+
+/// Joins the lines of a text into one, for task text from formats where it may span lines
+pub fn single_line(text:&str)->String{
+    text.split(['\r','\n']).map(|line| line.trim()).filter(|line| !line.is_empty()).join(" ")
+}
+
+/// Version of the Markdown layout written in the `# TaskList:` header, older files are upgraded by rusty_migrate
+pub const FORMAT_VERSION:u32=2;
+
+/// Pattern of a single Task line in the Markdown file
+pub const TASK_LINE_PATTERN:&str=r" - (\[[ √]\]) \[Due: (.*?)\] \[Completed: (.*?)\] (.*)";
+
+/// Pattern of one of the fields in front of the text of a Task line
+pub const TASK_FIELD_PATTERN:&str=r"^\[(Id|Deps|Recur|Priority|Created|Updated|Uid): (.*?)\] ";
+
+/// Dates are also read in the format files had before the storage_date_format setting, %.f keeps the fractional seconds
+const STORED_DATE_FORMAT:&str="%Y-%m-%d %H:%M:%S%.f %z";
+
+/// Writes a date for the Markdown file in the storage_date_format setting
+fn format_stored_date(date:DateTime<Utc>)->String{
+    date.with_timezone(&Local).format(&settings().storage_date_format).to_string()
+}
+
+/// Reads a date from the Markdown file, in the storage_date_format setting or the format older files have
+fn parse_stored_date(value:&str)->Option<DateTime<Utc>>{
+    DateTime::parse_from_str(value,&settings().storage_date_format)
+        .or_else(|_| DateTime::parse_from_str(value,STORED_DATE_FORMAT))
+        .ok()
+        .map(|date| date.to_utc())
+}
+
+/// Indent that marks a line as a note belonging to the Task above it
+pub const NOTE_INDENT:&str="    ";
+
+/// Convert tasklist to string
+pub fn convert_tltostring(tl:TaskList)->String{
+let eol = "\r\n";
+let mut result = String::new();
+result += eol;
+result += format!("# TaskList: [Version: {FORMAT_VERSION}]{eol}").as_str();
+for task in tl.tasks{
+    let tdata=task.data;
+    let tcompleted=task.completed;
+    let tdue_date=task.due_date.map(format_stored_date).unwrap_or_default();
+    let tcompleted_date=task.completed_date.map(format_stored_date).unwrap_or_default();
+    result += " - ";
+    result += match tcompleted{
+        true => "[√]",
+        false => "[ ]"
+    };
+    result += format!(" [Due: {tdue_date}]").as_str();
+    result += format!(" [Completed: {tcompleted_date}]").as_str();
+    result += format!(" [Id: {}]",task.id).as_str();
+    if !task.depends_on.is_empty() {
+        result += format!(" [Deps: {}]",task.depends_on.iter().join(",")).as_str();
+    }
+    if let Some(rule)=&task.recurrence {
+        result += format!(" [Recur: {rule}]").as_str();
+    }
+    if let Some(priority)=task.priority {
+        result += format!(" [Priority: {priority}]").as_str();
+    }
+    if let Some(value)=task.created_at {
+        result += format!(" [Created: {}]",format_stored_date(value)).as_str();
+    }
+    if let Some(value)=task.updated_at {
+        result += format!(" [Updated: {}]",format_stored_date(value)).as_str();
+    }
+    if let Some(uid)=&task.uid {
+        result += format!(" [Uid: {uid}]").as_str();
+    }
+    result += format!(" {}",escape_task_text(&tdata)).as_str();
+    //result += tcompleted.to_string().as_str();
+    result += eol;
+    // notes are kept as indented continuation lines under their Task
+    for note in task.notes{
+        result += format!("{NOTE_INDENT}{note}{eol}").as_str();
+    }
+}
+
+result
+}
+
+/// Convert string to tasklist
+pub fn convert_stringtotl(data:String)->TaskList{
+//println!("CONVERT STRING TO TL DATAIN:{}",data);
+// lines() also accepts files that an editor saved with plain \n endings
+let lines = data.lines();
+let mut tl:TaskList=TaskList{ tasks: Vec::new() };
+let mut tlfound=false;
+let re_full = Regex::new(TASK_LINE_PATTERN).unwrap();
+let _re_simple = Regex::new(r" - (\[[ √]\]) (.*)");
+let re_field = Regex::new(TASK_FIELD_PATTERN).unwrap();
+for line in lines{ 
+    if line.contains("# TaskList:"){
+        tlfound=true;
+    }
+    if tlfound{//even AFTER the line detected, this allows rest of code to run because its saved outside loop
+        // indented lines are notes of the last Task, check before the unanchored task regex
+        if let Some(note)=line.strip_prefix(NOTE_INDENT) {
+            if let Some(last_task)=tl.tasks.last_mut() {
+                last_task.notes.push(note.to_string());
+                continue
+            }
+        }
+        // convert - lines into Tasks   
+        let temp_task = match re_full.captures(line){
+            Some(captures)=>captures,
+            None=>continue //skip rest of loop
+        };
+        
+        let tcompleted_string:String=temp_task[1].to_string();
+        let tdue_date:String=temp_task[2].to_string();
+        let tcompleted_date:String=temp_task[3].to_string();
+        let (tfields,tdata)=split_task_fields(&re_field,&temp_task[4]);
+        
+        // convert brackets into completed/uncompleted
+        let tcompleted:bool = tcompleted_string.contains("[√]");
+
+        // build task
+        let mut new_task=Task::new(tcompleted,tdata);
+        new_task.id=tfields.get("Id").and_then(|v| v.parse().ok()).unwrap_or(0);
+        new_task.depends_on=tfields.get("Deps")
+                                  .map(|v| v.split(',').filter_map(|id| id.trim().parse().ok()).collect())
+                                  .unwrap_or_default();
+        new_task.recurrence=tfields.get("Recur").and_then(|v| v.parse().ok());
+        new_task.priority=tfields.get("Priority").and_then(|v| parse_priority(v));
+        new_task.uid=tfields.get("Uid").cloned();
+
+        // date management
+        // always convert from LOCAL string, to UTC struct
+        new_task.due_date=parse_stored_date(&tdue_date);
+        new_task.completed_date=parse_stored_date(&tcompleted_date);
+        new_task.created_at=tfields.get("Created").and_then(|v| parse_stored_date(v));
+        new_task.updated_at=tfields.get("Updated").and_then(|v| parse_stored_date(v));
+
+        // correct disparity between CHECK completed and COMPLETED date
+        if new_task.completed_date.is_none() && tcompleted {
+            new_task.completed_date = Some(Utc::now())
+        }
+
+        // task building complete
+        tl.tasks.push(new_task);
+    }
+}
+// files written before ids existed get them assigned here
+tl.ensure_ids();
+// Return TaskList, if one was not found we return an empty TaskList
+tl
+}
+
+/// Every line convert_stringtotl would skip or only partly read, with its 1-based number and the reason.
+/// Saving a list loaded from such a file would drop what was not understood.
+pub fn find_parse_problems(data:&str)->Vec<(usize,String)>{
+    let re_full = Regex::new(TASK_LINE_PATTERN).unwrap();
+    let re_field = Regex::new(TASK_FIELD_PATTERN).unwrap();
+    let mut problems:Vec<(usize,String)>=Vec::new();
+    let Some(header)=data.lines().position(|line| line.contains("# TaskList:")) else {
+        if !data.trim().is_empty() {
+            problems.push((1,"there is no '# TaskList:' header, so nothing in the file is read".to_string()));
+        }
+        return problems
+    };
+    let bad_date=|name:&str,value:&str| (!value.trim().is_empty() && parse_stored_date(value).is_none())
+        .then(|| format!("{name} '{value}' is not a date like 2024-03-30 00:00:00 +00:00"));
+    let mut task_seen=false;
+    for (i,line) in data.lines().enumerate(){
+        let number=i+1;
+        if i == header {
+            match markdown_version(line) {
+                Ok(version) if version > FORMAT_VERSION=>problems.push((number,format!("format {version} is newer than this version reads ({FORMAT_VERSION})"))),
+                Ok(_)=>{},
+                Err(error)=>problems.push((number,error))
+            }
+            continue
+        }
+        if line.trim().is_empty() || (line.starts_with(NOTE_INDENT) && task_seen) {
+            continue
+        }
+        if i < header {
+            problems.push((number,format!("'{}' is before the '# TaskList:' header",line.trim())));
+            continue
+        }
+        let Some(captures)=re_full.captures(line) else {
+            let reason=if line.starts_with(NOTE_INDENT) { "is a note with no task above it" } else { "is not a task line" };
+            problems.push((number,format!("'{}' {reason}",line.trim())));
+            continue
+        };
+        task_seen=true;
+        let (fields,_)=split_task_fields(&re_field,&captures[4]);
+        let mut reasons:Vec<String>=[("due date",&captures[2]),("completed date",&captures[3])].into_iter()
+            .chain(fields.get("Created").map(|v| ("created date",v.as_str())))
+            .chain(fields.get("Updated").map(|v| ("updated date",v.as_str())))
+            .filter_map(|(name,value)| bad_date(name,value))
+            .collect();
+        if let Some(id)=fields.get("Id").filter(|id| id.trim().parse::<usize>().is_err()) {
+            reasons.push(format!("id '{id}' is not a number"));
+        }
+        if let Some(deps)=fields.get("Deps") {
+            reasons.extend(deps.split(',').filter(|id| id.trim().parse::<usize>().is_err()).map(|id| format!("dependency '{id}' is not a task id")));
+        }
+        if let Some(Err(error))=fields.get("Recur").map(|rule| rule.parse::<Recurrence>()) {
+            reasons.push(format!("repeat rule: {error}"));
+        }
+        if let Some(priority)=fields.get("Priority").filter(|p| parse_priority(p).is_none()) {
+            reasons.push(format!("priority '{priority}' is not a letter"));
+        }
+        problems.extend(reasons.into_iter().map(|reason| (number,reason)));
+    }
+    problems
+}
+
+/// One `Line N: reason` line per problem found by find_parse_problems
+pub fn format_parse_problems(problems:&[(usize,String)])->String{
+    problems.iter().map(|(number,reason)| format!("Line {number}: {reason}")).join("\r\n")
+}
+
+/// Reads a priority letter, A to Z in either case
+pub fn parse_priority(value:&str)->Option<char>{
+    let mut chars=value.trim().chars();
+    match (chars.next(),chars.next()) {
+        (Some(c),None) if c.is_ascii_alphabetic()=>Some(c.to_ascii_uppercase()),
+        _=>None
+    }
+}
+
+/// Reads a calendar UID to keep, None when it is empty or holds a ']' that would end its Markdown field
+pub fn parse_uid(value:&str)->Option<String>{
+    Some(value.trim().to_string()).filter(|uid| !uid.is_empty() && !uid.contains(']'))
+}
+
+/// Reads the priority of a JSON or YAML Task with parse_priority, anything but a letter is an error
+fn deserialize_priority<'de,D:serde::Deserializer<'de>>(deserializer:D)->Result<Option<char>,D::Error>{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(value)=>parse_priority(&value).map(Some).ok_or_else(|| serde::de::Error::custom(format!("priority '{value}' is not a letter"))),
+        None=>Ok(None)
+    }
+}
+
+/// Puts a backslash in front of task text that starts with `[` or `\`, so it is never read back as a field
+pub fn escape_task_text(text:&str)->String{
+    if text.starts_with(['[','\\']) { format!("\\{text}") } else { text.to_string() }
+}
+
+/// Strips leading `[Key: value]` fields off the text of a Task line, and the backslash escape_task_text put in front of it
+fn split_task_fields(re_field:&Regex,text:&str)->(HashMap<String,String>,String){
+    let mut fields=HashMap::new();
+    let mut rest=text;
+    while let Some(captures)=re_field.captures(rest){
+        fields.insert(captures[1].to_string(),captures[2].to_string());
+        rest=&rest[captures[0].len()..];
+    }
+    (fields,rest.strip_prefix('\\').unwrap_or(rest).to_string())
 }
\ No newline at end of file
@@ -0,0 +1,114 @@
+use std::io::IsTerminal;
+
+use colored::Color;
+
+use crate::rusty_config::{parse_color, settings, Colors, Config};
+
+/// Names of the themes that come with the app
+pub const BUILTIN_THEMES:[&str;2]=["dark","light"];
+
+/// Colours of one built-in theme
+fn builtin_theme(name:&str)->Option<Colors>{
+    let colors=|values:[&str;10]|{
+        let [label,text,done,todo,due,overdue,completed,recurrence,blocked,muted]=values.map(|v| Some(v.to_string()));
+        Colors{ label, text, done, todo, due, overdue, completed, recurrence, blocked, muted }
+    };
+    match name {
+        "dark"=>Some(colors(["purple","white","green","red","yellow","red","green","cyan","red","125,125,125"])),
+        "light"=>Some(colors(["blue","black","0,128,0","red","150,90,0","red","0,128,0","0,110,130","red","110,110,110"])),
+        _=>None
+    }
+}
+
+/// Colours of a theme by name, user themes fill their gaps from the dark theme
+pub fn theme_colors(config:&Config,name:&str)->Option<Colors>{
+    match config.themes.get(name) {
+        Some(user)=>builtin_theme("dark").map(|dark| dark.overlay(user)),
+        None=>builtin_theme(name)
+    }
+}
+
+/// Every theme the config can pick
+pub fn theme_names(config:&Config)->Vec<String>{
+    let mut names:Vec<String>=BUILTIN_THEMES.iter().map(|n| n.to_string()).collect();
+    names.extend(config.themes.keys().filter(|n| !BUILTIN_THEMES.contains(&n.as_str())).cloned());
+    names
+}
+
+/// The chosen theme with the [colors] overrides applied, every colour is set
+pub fn effective_colors(config:&Config)->Colors{
+    let theme=theme_colors(config,&config.theme).or_else(|| builtin_theme("dark")).unwrap_or_default();
+    theme.overlay(&config.colors)
+}
+
+/// Colours ready for printing
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Palette{
+    pub label: Color,
+    pub text: Color,
+    pub done: Color,
+    pub todo: Color,
+    pub due: Color,
+    pub overdue: Color,
+    pub completed: Color,
+    pub recurrence: Color,
+    pub blocked: Color,
+    pub muted: Color
+}
+
+impl Palette{
+    pub fn from_config(config:&Config)->Palette{
+        let colors=effective_colors(config);
+        // validation at startup means the fallback is only hit by a broken default theme
+        let get=|value:&Option<String>| value.as_deref().and_then(|v| parse_color(v).ok()).unwrap_or(Color::White);
+        Palette{
+            label:get(&colors.label),
+            text:get(&colors.text),
+            done:get(&colors.done),
+            todo:get(&colors.todo),
+            due:get(&colors.due),
+            overdue:get(&colors.overdue),
+            completed:get(&colors.completed),
+            recurrence:get(&colors.recurrence),
+            blocked:get(&colors.blocked),
+            muted:get(&colors.muted)
+        }
+    }
+}
+
+/// The palette of the config in use
+pub fn palette()->Palette{
+    Palette::from_config(&settings())
+}
+
+/// Turns colour on or off for all output: always, never, or auto which follows NO_COLOR and whether stdout is a terminal
+pub fn apply_color_mode(mode:&str){
+    let enabled=match mode {
+        "always"=>true,
+        "never"=>false,
+        _=>std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && std::io::stdout().is_terminal()
+    };
+    colored::control::set_override(enabled);
+}
+
+/// Whether colour is on, as apply_color_mode left it
+pub fn color_enabled()->bool{
+    colored::control::SHOULD_COLORIZE.should_colorize()
+}
+
+/// Whether to draw non-ASCII symbols, auto looks for UTF-8 in the locale
+pub fn use_unicode()->bool{
+    match settings().unicode.as_str() {
+        "always"=>true,
+        "never"=>false,
+        _=>cfg!(windows) || ["LC_ALL","LC_CTYPE","LANG"].iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .is_some_and(|value| value.to_uppercase().replace('-',"").contains("UTF8"))
+    }
+}
+
+/// The mark inside a completed Task's checkbox
+pub fn check_mark()->&'static str{
+    if use_unicode() { "√" } else { "x" }
+}
@@ -0,0 +1,107 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+
+use crate::rusty_tasks::*;
+
+/// Dates in todo.txt are plain days
+const TODOTXT_DATE_FORMAT:&str="%Y-%m-%d";
+
+/// A day at local midnight, the same way typed due dates are stored
+fn local_day(day:NaiveDate)->DateTime<Utc>{
+    let midnight=day.and_time(NaiveTime::default());
+    match Local.from_local_datetime(&midnight).earliest() {
+        Some(value)=>value.with_timezone(&Utc),
+        None=>midnight.and_utc()
+    }
+}
+
+fn parse_day(word:&str)->Option<DateTime<Utc>>{
+    NaiveDate::parse_from_str(word,TODOTXT_DATE_FORMAT).ok().map(local_day)
+}
+
+fn format_day(date:DateTime<Utc>)->String{
+    date.with_timezone(&Local).format(TODOTXT_DATE_FORMAT).to_string()
+}
+
+/// Reads `(A)` at the start of a line
+fn parse_priority_word(word:&str)->Option<char>{
+    word.strip_prefix('(')?.strip_suffix(')').and_then(parse_priority)
+}
+
+/// Reads one todo.txt line, `x 2024-03-01 2024-02-20 (A) Call mum +family @phone due:2024-03-05`
+pub fn parse_todotxt_line(line:&str)->Option<Task>{
+    let mut words=line.split_whitespace().peekable();
+    words.peek()?;
+    let mut task=Task::new(false,String::new());
+
+    if words.next_if_eq(&"x").is_some() {
+        task.completed=true;
+        task.completed_date=words.peek().and_then(|w| parse_day(w));
+        if task.completed_date.is_some() {
+            words.next();
+        }
+    }
+    if let Some(priority)=words.peek().and_then(|w| parse_priority_word(w)) {
+        task.priority=Some(priority);
+        words.next();
+    }
+    // a creation date is only allowed after the completion date on done tasks
+    if !task.completed || task.completed_date.is_some() {
+        task.created_at=words.peek().and_then(|w| parse_day(w));
+        if task.created_at.is_some() {
+            words.next();
+        }
+    }
+
+    let mut text:Vec<&str>=Vec::new();
+    for word in words{
+        match word.split_once(':') {
+            Some(("due",value)) if parse_day(value).is_some()=>task.due_date=parse_day(value),
+            // done tasks lose their (A), most tools keep it as pri:A
+            Some(("pri",value)) if parse_priority(value).is_some()=>task.priority=parse_priority(value),
+            _=>text.push(word)
+        }
+    }
+    task.data=text.join(" ");
+    if task.completed && task.completed_date.is_none() {
+        task.completed_date=Some(Utc::now());
+    }
+    Some(task)
+}
+
+/// Writes one Task as a todo.txt line
+pub fn format_todotxt_line(task:&Task)->String{
+    let mut words:Vec<String>=Vec::new();
+    if task.completed {
+        words.push("x".to_string());
+        if let Some(date)=task.completed_date {
+            words.push(format_day(date));
+        }
+    }else if let Some(priority)=task.priority {
+        words.push(format!("({priority})"));
+    }
+    if let Some(date)=task.created_at.filter(|_| !task.completed || task.completed_date.is_some()) {
+        words.push(format_day(date));
+    }
+    if !task.data.is_empty() {
+        words.push(task.data.clone());
+    }
+    if let Some(date)=task.due_date {
+        words.push(format!("due:{}",format_day(date)));
+    }
+    if let (true,Some(priority))=(task.completed,task.priority) {
+        words.push(format!("pri:{priority}"));
+    }
+    words.join(" ")
+}
+
+/// Convert tasklist to todo.txt, one Task per line; notes, dependencies and repeats have no place there
+pub fn convert_tltotodotxt(tl:&TaskList)->String{
+    tl.tasks.iter().map(|task| format_todotxt_line(task)+"\n").collect()
+}
+
+/// Convert todo.txt to tasklist, blank lines are skipped
+pub fn convert_todotxttotl(data:&str)->TaskList{
+    let mut tl=TaskList{ tasks: data.lines().filter_map(parse_todotxt_line).collect() };
+    tl.ensure_ids();
+    tl
+}
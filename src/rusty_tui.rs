@@ -0,0 +1,341 @@
+use std::io::{self, Stdout, Write};
+use chrono::Local;
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use crate::rusty_tasks::*;
+use crate::rusty_commands::*;
+use crate::rusty_agenda::is_overdue;
+use crate::rusty_theme::{check_mark, color_enabled, palette};
+use crate::rusty_store::TaskStore;
+
+/// What the keyboard is currently typing into
+#[derive(Default,Debug,Clone,PartialEq)]
+pub enum TuiMode{
+    #[default]
+    Normal,
+    Filter,
+    Add,
+    Edit,
+    ConfirmDelete
+}
+
+/// Represents the screen state of the full-screen UI, separate from the TaskList it shows
+#[derive(Default,Debug,Clone)]
+pub struct TuiState{
+    pub selected: usize,
+    pub offset: usize,
+    pub filter: String,
+    pub input: String,
+    pub mode: TuiMode,
+    pub show_detail: bool,
+    pub message: String
+}
+
+impl TuiState{
+    /// Shows what a command had to say in the status line, or `done` when it had nothing to add
+    pub fn report(&mut self,result:Result<Vec<String>,String>,done:&str){
+        self.message=match result {
+            Ok(messages) if messages.is_empty()=>done.to_string(),
+            Ok(messages)=>messages.join("  "),
+            Err(error)=>error
+        };
+    }
+
+    /// Moves the selection by delta rows, clamped to the visible Tasks
+    pub fn move_selection(&mut self,delta:isize,visible_len:usize){
+        if visible_len == 0 {
+            self.selected=0;
+            return
+        }
+        let max=visible_len as isize-1;
+        self.selected=(self.selected as isize+delta).clamp(0,max) as usize;
+    }
+
+    /// Scrolls so the selected row stays inside a window of `height` rows
+    pub fn scroll_into_view(&mut self,height:usize){
+        if height == 0 {
+            return
+        }
+        if self.selected < self.offset {
+            self.offset=self.selected;
+        }else if self.selected >= self.offset+height {
+            self.offset=self.selected+1-height;
+        }
+    }
+}
+
+/// Indexes of Tasks whose text or notes contain the filter, ignoring case
+pub fn visible_tasks(tl:&TaskList,filter:&str)->Vec<usize>{
+    let needle=filter.trim().to_lowercase();
+    (0..tl.tasks.len())
+        .filter(|i|{
+            let task=&tl.tasks[*i];
+            needle.is_empty()
+                || task.data.to_lowercase().contains(&needle)
+                || task.notes.iter().any(|note| note.to_lowercase().contains(&needle))
+        })
+        .collect()
+}
+
+/// Restores the terminal even when the UI returns early or panics
+struct TerminalGuard;
+
+impl TerminalGuard{
+    fn enter(stdout:&mut Stdout)->io::Result<TerminalGuard>{
+        enable_raw_mode()?;
+        queue!(stdout,EnterAlternateScreen,Hide)?;
+        stdout.flush()?;
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard{
+    fn drop(&mut self){
+        let mut stdout=io::stdout();
+        let _ = queue!(stdout,Show,LeaveAlternateScreen);
+        let _ = stdout.flush();
+        let _ = disable_raw_mode();
+    }
+}
+
+/// Cuts text to at most `width` characters
+fn fit(text:&str,width:usize)->String{
+    text.chars().take(width).collect()
+}
+
+/// Starts the full-screen UI; every change goes through the same commands the REPL uses
+pub fn run_tui(global_tasks:&mut TaskList,store:&mut dyn TaskStore)->io::Result<()>{
+    let mut stdout=io::stdout();
+    let _guard=TerminalGuard::enter(&mut stdout)?;
+    let mut state=TuiState::default();
+
+    loop{
+        let visible=visible_tasks(global_tasks,&state.filter);
+        state.move_selection(0,visible.len());
+        draw(&mut stdout,global_tasks,&visible,&mut state)?;
+
+        let Event::Key(key)=event::read()? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue
+        }
+        if !handle_key(key,global_tasks,&visible,&mut state,store) {
+            return Ok(())
+        }
+    }
+}
+
+/// Applies one key press, returns false when the user asked to quit
+fn handle_key(key:KeyEvent,global_tasks:&mut TaskList,visible:&[usize],state:&mut TuiState,store:&mut dyn TaskStore)->bool{
+    let selected_index=visible.get(state.selected).copied();
+    state.message.clear();
+
+    match state.mode {
+        TuiMode::Normal=>match key.code {
+            KeyCode::Char('q') | KeyCode::Esc=>return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL)=>return false,
+            KeyCode::Down | KeyCode::Char('j')=>state.move_selection(1,visible.len()),
+            KeyCode::Up | KeyCode::Char('k')=>state.move_selection(-1,visible.len()),
+            KeyCode::PageDown=>state.move_selection(10,visible.len()),
+            KeyCode::PageUp=>state.move_selection(-10,visible.len()),
+            KeyCode::Home | KeyCode::Char('g')=>state.selected=0,
+            KeyCode::End | KeyCode::Char('G')=>state.move_selection(visible.len() as isize,visible.len()),
+            KeyCode::Enter | KeyCode::Tab=>state.show_detail = !state.show_detail,
+            KeyCode::Char(' ') | KeyCode::Char('x')=>{
+                if let Some(index)=selected_index {
+                    state.report(command_complete(global_tasks,index+1,store),"");
+                }
+            },
+            KeyCode::Char('/')=>{
+                state.input=state.filter.clone();
+                state.mode=TuiMode::Filter;
+            },
+            KeyCode::Char('a')=>{
+                state.input.clear();
+                state.mode=TuiMode::Add;
+            },
+            KeyCode::Char('e')=>{
+                if let Some(index)=selected_index {
+                    state.input=global_tasks.tasks[index].data.clone();
+                    state.mode=TuiMode::Edit;
+                }
+            },
+            KeyCode::Char('d') | KeyCode::Delete if selected_index.is_some()=>state.mode=TuiMode::ConfirmDelete,
+            _=>{}
+        },
+        TuiMode::ConfirmDelete=>{
+            if let (KeyCode::Char('y'),Some(index))=(key.code,selected_index) {
+                state.report(command_remove(global_tasks,index+1,store),"Task removed.");
+            }
+            state.mode=TuiMode::Normal;
+        },
+        TuiMode::Filter | TuiMode::Add | TuiMode::Edit=>match key.code {
+            KeyCode::Esc=>{
+                if state.mode == TuiMode::Filter {
+                    state.filter.clear();
+                }
+                state.mode=TuiMode::Normal;
+            },
+            KeyCode::Backspace=>{
+                state.input.pop();
+                if state.mode == TuiMode::Filter {
+                    state.filter=state.input.clone();
+                }
+            },
+            KeyCode::Char(c)=>{
+                state.input.push(c);
+                if state.mode == TuiMode::Filter {
+                    state.filter=state.input.clone();
+                    state.selected=0;
+                }
+            },
+            KeyCode::Enter=>{
+                let text=state.input.trim().to_string();
+                let result=match (&state.mode,selected_index) {
+                    (TuiMode::Add,_) if !text.is_empty()=>command_add(global_tasks,text,"".to_string(),store),
+                    (TuiMode::Edit,Some(index)) if !text.is_empty()=>command_edit(global_tasks,index+1,text,store),
+                    _=>Ok(Vec::new())
+                };
+                state.report(result,"");
+                state.mode=TuiMode::Normal;
+            },
+            _=>{}
+        }
+    }
+    true
+}
+
+/// Draws the title bar, task list, optional detail pane and the status line
+fn draw(stdout:&mut Stdout,global_tasks:&TaskList,visible:&[usize],state:&mut TuiState)->io::Result<()>{
+    let (cols,rows)=terminal::size()?;
+    let (cols,rows)=(cols as usize,rows as usize);
+    let wide=cols >= 80;
+    let list_width=if state.show_detail && wide { cols*3/5 } else { cols };
+    let body_height=rows.saturating_sub(3);
+    let list_height=if state.show_detail && !wide { body_height/2 } else { body_height };
+    state.scroll_into_view(list_height);
+    let colors=palette();
+
+    queue!(stdout,Clear(ClearType::All),MoveTo(0,0))?;
+    let filter_string=match state.filter.is_empty() {
+        true=>String::new(),
+        false=>format!("  filter: {}",state.filter)
+    };
+    let title=format!(" Rusty Tasks  {}/{} tasks{}",visible.len(),global_tasks.tasks.len(),filter_string);
+    queue!(stdout,SetAttribute(Attribute::Reverse),Print(format!("{:<width$}",fit(&title,cols),width=cols)),SetAttribute(Attribute::Reset))?;
+
+    for row in 0..list_height{
+        let Some(index)=visible.get(state.offset+row).copied() else { break };
+        let task=&global_tasks.tasks[index];
+        let checkbox=if task.completed { format!("[{}]",check_mark()) } else { "[ ]".to_string() };
+        let due=task.due_date.map(|d| d.with_timezone(&Local).format("%Y-%m-%d").to_string()).unwrap_or_default();
+        let blocked=if !task.completed && global_tasks.is_blocked(index) { " (blocked)" } else { "" };
+        let text_width=list_width.saturating_sub(18);
+        let line=format!("{:>3} {checkbox} {:<text_width$} {due:>10}",index+1,fit(&format!("{}{blocked}",task.data),text_width));
+
+        queue!(stdout,MoveTo(0,(row+1) as u16))?;
+        if state.offset+row == state.selected {
+            queue!(stdout,SetAttribute(Attribute::Reverse))?;
+        }
+        let color=match task {
+            t if t.completed=>colors.muted,
+            t if is_overdue(t)=>colors.overdue,
+            _ if !blocked.is_empty()=>colors.blocked,
+            _=>colors.text
+        };
+        set_color(stdout,color)?;
+        queue!(stdout,Print(fit(&line,list_width)),ResetColor,SetAttribute(Attribute::Reset))?;
+    }
+
+    if state.show_detail {
+        if let Some(index)=visible.get(state.selected).copied() {
+            let (x,y,width,height)=match wide {
+                true=>(list_width+1,1,cols-list_width-1,body_height),
+                false=>(0,list_height+1,cols,body_height-list_height)
+            };
+            for (i,line) in detail_lines(global_tasks,index).iter().take(height).enumerate(){
+                queue!(stdout,MoveTo(x as u16,(y+i) as u16),Print(fit(line,width)))?;
+            }
+        }
+    }
+
+    let status=match state.mode {
+        TuiMode::Normal if !state.message.is_empty()=>state.message.clone(),
+        TuiMode::Normal=>String::new(),
+        TuiMode::Filter=>format!("/{}",state.input),
+        TuiMode::Add=>format!("Add: {}",state.input),
+        TuiMode::Edit=>format!("Edit: {}",state.input),
+        TuiMode::ConfirmDelete=>"Remove this task? (y/n)".to_string()
+    };
+    let help=" ↑↓/jk move  space toggle  a add  e edit  d remove  / filter  enter details  q quit";
+    queue!(stdout,MoveTo(0,rows.saturating_sub(2) as u16),Print(fit(&status,cols)))?;
+    queue!(stdout,MoveTo(0,rows.saturating_sub(1) as u16))?;
+    set_color(stdout,colors.muted)?;
+    queue!(stdout,Print(fit(help,cols)),ResetColor)?;
+    stdout.flush()
+}
+
+/// Sets the text colour from a palette colour, or leaves it alone when colour is off
+fn set_color(stdout:&mut Stdout,color:colored::Color)->io::Result<()>{
+    if !color_enabled() {
+        return Ok(())
+    }
+    queue!(stdout,SetForegroundColor(term_color(color)))
+}
+
+/// The crossterm colour for a palette colour
+pub fn term_color(color:colored::Color)->Color{
+    match color {
+        colored::Color::Black=>Color::Black,
+        colored::Color::Red=>Color::DarkRed,
+        colored::Color::Green=>Color::DarkGreen,
+        colored::Color::Yellow=>Color::DarkYellow,
+        colored::Color::Blue=>Color::DarkBlue,
+        colored::Color::Magenta=>Color::DarkMagenta,
+        colored::Color::Cyan=>Color::DarkCyan,
+        colored::Color::White=>Color::Grey,
+        colored::Color::BrightBlack=>Color::DarkGrey,
+        colored::Color::BrightRed=>Color::Red,
+        colored::Color::BrightGreen=>Color::Green,
+        colored::Color::BrightYellow=>Color::Yellow,
+        colored::Color::BrightBlue=>Color::Blue,
+        colored::Color::BrightMagenta=>Color::Magenta,
+        colored::Color::BrightCyan=>Color::Cyan,
+        colored::Color::BrightWhite=>Color::White,
+        colored::Color::TrueColor{ r, g, b }=>Color::Rgb{ r, g, b }
+    }
+}
+
+/// Plain text lines for the detail pane of the Task at index
+fn detail_lines(global_tasks:&TaskList,index:usize)->Vec<String>{
+    let task=&global_tasks.tasks[index];
+    let format_date=|date:Option<chrono::DateTime<chrono::Utc>>| date.map(|d| d.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()).unwrap_or("N/A".to_string());
+    let mut lines=vec![
+        format!("Task {} (id {})",index+1,task.id),
+        task.data.clone(),
+        String::new(),
+        format!("Due:       {}",format_date(task.due_date)),
+        format!("Completed: {}",format_date(task.completed_date)),
+        format!("Created:   {}",format_date(task.created_at)),
+        format!("Updated:   {}",format_date(task.updated_at)),
+    ];
+    if let Some(rule)=&task.recurrence {
+        lines.push(format!("Repeats:   {rule}"));
+    }
+    let open=global_tasks.open_prerequisites(index);
+    if !open.is_empty() {
+        let blocked_by=open.iter().map(|i| format!("#{}",i+1)).collect::<Vec<_>>().join(", ");
+        lines.push(format!("Blocked by: {blocked_by}"));
+    }
+    if !task.notes.is_empty() {
+        lines.push(String::new());
+        lines.push("Notes:".to_string());
+        lines.extend(task.notes.iter().map(|note| format!("  {note}")));
+    }
+    lines
+}
@@ -0,0 +1,125 @@
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+use crate::rusty_config::settings;
+
+/// The list kept in the data_file setting, it always exists and can not be renamed or deleted
+pub const DEFAULT_LIST:&str="default";
+
+/// Extensions a list file may have, the same ones storage_format_for knows
+const LIST_EXTENSIONS:[&str;8]=["md","markdown","json","yaml","yml","db","sqlite","sqlite3"];
+
+/// Named task lists: the data_file setting is the default list and every other list is a file in lists_dir
+pub struct Workspace{
+    pub lists_dir: String,
+    pub data_file: String,
+    /// Picks the extension of new lists
+    pub storage_format: String
+}
+
+impl Workspace{
+    pub fn from_settings()->Workspace{
+        let settings=settings();
+        Workspace{
+            lists_dir:settings.lists_dir.clone(),
+            data_file:settings.data_file.clone(),
+            storage_format:settings.storage_format.clone()
+        }
+    }
+
+    /// True for the existing files a save may replace: those in ./data, the lists in lists_dir and
+    /// the data_file, wherever the config puts them
+    pub fn may_overwrite(&self,filepath:&str)->bool{
+        let Ok(filepath)=Path::new(filepath).canonicalize() else {
+            return false
+        };
+        let data_directory=std::env::current_dir().map(|dir| dir.join("data")).unwrap_or_default();
+        [data_directory,PathBuf::from(&self.lists_dir)].iter()
+            .filter_map(|directory| directory.canonicalize().ok())
+            .any(|directory| filepath.starts_with(directory))
+            || Path::new(&self.data_file).canonicalize().is_ok_and(|data_file| data_file == filepath)
+    }
+
+    /// Where the name of the active list is kept between runs
+    fn active_list_file(&self)->PathBuf{
+        Path::new(&self.lists_dir).join(".active")
+    }
+
+    /// The file of a list, the one it already has or else a new one in the storage_format setting
+    pub fn list_path(&self,name:&str)->String{
+        if name == DEFAULT_LIST {
+            return self.data_file.clone()
+        }
+        LIST_EXTENSIONS.iter()
+            .map(|extension| format!("{}/{name}.{extension}",self.lists_dir))
+            .find(|path| Path::new(path).exists())
+            .unwrap_or_else(|| format!("{}/{name}.{}",self.lists_dir,extension_for(&self.storage_format)))
+    }
+
+    pub fn list_exists(&self,name:&str)->bool{
+        name == DEFAULT_LIST || Path::new(&self.list_path(name)).exists()
+    }
+
+    /// Every list by name, the default list first and the others sorted
+    pub fn list_names(&self)->Vec<String>{
+        let mut names:Vec<String>=fs::read_dir(&self.lists_dir)
+            .map(|entries| entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|e| LIST_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str())))
+                .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+                .filter(|name| name != DEFAULT_LIST && validate_list_name(name).is_ok())
+                .collect())
+            .unwrap_or_default();
+        names.sort();
+        names.dedup();
+        names.insert(0,DEFAULT_LIST.to_string());
+        names
+    }
+
+    /// The name of the list kept in a file, or the file itself when it is not one of the lists
+    pub fn list_name_for(&self,filepath:&str)->String{
+        let path=Path::new(filepath);
+        if filepath == self.data_file {
+            return DEFAULT_LIST.to_string()
+        }
+        match (path.parent(),path.file_stem()) {
+            (Some(parent),Some(stem)) if parent == Path::new(&self.lists_dir)=>stem.to_string_lossy().to_string(),
+            _=>filepath.to_string()
+        }
+    }
+
+    /// The file of the list that was active in the last run, the default list when it is gone
+    pub fn active_list_path(&self)->String{
+        let name=fs::read_to_string(self.active_list_file()).unwrap_or_default().trim().to_string();
+        if validate_list_name(&name).is_ok() && self.list_exists(&name) {
+            self.list_path(&name)
+        }else{
+            self.data_file.clone()
+        }
+    }
+
+    /// Remembers the active list for the next run
+    pub fn remember_active_list(&self,name:&str)->Result<(),Error>{
+        fs::create_dir_all(&self.lists_dir)?;
+        fs::write(self.active_list_file(),format!("{name}\n"))
+    }
+}
+
+/// List names become file names, so they are kept to letters, digits, - and _
+pub fn validate_list_name(name:&str)->Result<(),String>{
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("'{name}' is not a list name, use letters, digits, - and _"))
+    }
+    Ok(())
+}
+
+/// The extension new files of a storage format get
+fn extension_for(storage_format:&str)->&'static str{
+    match storage_format {
+        "json"=>"json",
+        "yaml"=>"yaml",
+        "sqlite"=>"db",
+        _=>"md"
+    }
+}